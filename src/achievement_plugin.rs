@@ -0,0 +1,239 @@
+use std::{
+    path::PathBuf,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use bevy::prelude::*;
+
+use crate::{
+    achievements::{parse_achievements, Achievement, Progress, UnlockedAchievements, ACHIEVEMENTS},
+    snake::SnakeGame,
+    snake_plugin::{
+        components::{ThemeColor, ThemedTextComponent},
+        events::{
+            FoodAteEvent, GameOverEvent, GameTick, RestartEvent, RewindEvent, SnakeMovedEvent,
+        },
+        resources::{GameTimerResource, HistoryResource, LocalPlayersResource, ThemeResource},
+    },
+    DataDir,
+};
+
+use self::{
+    components::ToastComponent,
+    resources::{AchievementsResource, ProgressResource},
+};
+
+/// How long an unlocked achievement is announced
+const TOAST_SECONDS: f32 = 3.;
+
+/// Unlocks the achievements reached by the local players, announcing them with a toast.
+/// Needs `SnakePlugin` and `DataDir`
+pub struct AchievementPlugin;
+
+impl Plugin for AchievementPlugin {
+    fn build(&self, app: &mut App) {
+        let achievements = parse_achievements(ACHIEVEMENTS).expect("invalid achievements");
+        let path = app
+            .world
+            .resource::<DataDir>()
+            .0
+            .as_deref()
+            .map(UnlockedAchievements::path);
+        let unlocked = match &path {
+            None => {
+                warn!("No data directory: the achievements are not kept");
+                UnlockedAchievements::default()
+            }
+            Some(path) => UnlockedAchievements::load(path).unwrap_or_else(|e| {
+                warn!("Achievements not loaded: {}", e);
+                UnlockedAchievements::default()
+            }),
+        };
+        let snakes = app.world.resource::<SnakeGame>().snake_count();
+
+        app.insert_resource(AchievementsResource {
+            achievements,
+            path,
+            unlocked,
+        })
+        .insert_resource(ProgressResource(vec![Progress::default(); snakes]))
+        .add_system(reset_progress.label("progress").after("play"))
+        .add_system(track_moves.label("progress").after("play"))
+        // The foods eaten are told once the food is moved
+        .add_system(track_foods.label("progress").after("update_food"))
+        .add_system(track_wins.label("progress").after("play"))
+        .add_system(unlock_achievements.after("progress"))
+        .add_system(hide_toasts);
+    }
+}
+
+/// Going back lets the player try again, not keep the progress
+fn reset_progress(
+    mut restart_event_reader: EventReader<RestartEvent>,
+    mut rewind_event_reader: EventReader<RewindEvent>,
+    mut progress: ResMut<ProgressResource>,
+) {
+    let restarted = restart_event_reader.iter().count() > 0;
+    let rewound = rewind_event_reader.iter().count() > 0;
+    if restarted || rewound {
+        progress.0.fill(Progress::default());
+    }
+}
+
+fn track_moves(
+    mut snake_moved_event_reader: EventReader<SnakeMovedEvent>,
+    game_timers: Res<GameTimerResource>,
+    mut progress: ResMut<ProgressResource>,
+) {
+    for event in snake_moved_event_reader.iter() {
+        let progress = &mut progress.0[event.player];
+        if event.turned {
+            progress.turns += 1;
+        }
+        progress.length = event.length;
+        progress.survived += game_timers.0.duration();
+    }
+}
+
+fn track_foods(
+    mut food_ate_event_reader: EventReader<FoodAteEvent>,
+    mut progress: ResMut<ProgressResource>,
+) {
+    for event in food_ate_event_reader.iter() {
+        progress.0[event.player].foods += 1;
+    }
+}
+
+fn track_wins(
+    mut game_over_event_reader: EventReader<GameOverEvent>,
+    mut progress: ResMut<ProgressResource>,
+) {
+    for event in game_over_event_reader.iter() {
+        if let Some(winner) = event.winner {
+            progress.0[winner].won = true;
+        }
+    }
+}
+
+fn unlock_achievements(
+    mut commands: Commands,
+    theme: Res<ThemeResource>,
+    // The game is over in a tick too
+    mut tick_event_reader: EventReader<GameTick>,
+    history: Res<HistoryResource>,
+    local_players: Res<LocalPlayersResource>,
+    progress: Res<ProgressResource>,
+    mut achievements: ResMut<AchievementsResource>,
+) {
+    // A rewound game unlocks nothing till restarted
+    if tick_event_reader.iter().count() == 0 || history.rewound {
+        return;
+    }
+
+    let date = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    // The walls never move
+    let open_cells = history.initial.open_cells();
+
+    let achievements = &mut *achievements;
+    let mut unlocked: Vec<&Achievement> = vec![];
+    for id in &local_players.0 {
+        unlocked.extend(achievements.unlocked.unlock(
+            &achievements.achievements,
+            &progress.0[id.0],
+            open_cells,
+            date,
+        ));
+    }
+    if unlocked.is_empty() {
+        return;
+    }
+
+    if let Some(path) = &achievements.path {
+        if let Err(e) = achievements.unlocked.save(path) {
+            warn!("Unable to save the achievements: {}", e);
+        }
+    }
+    for (index, achievement) in unlocked.into_iter().enumerate() {
+        info!("Achievement unlocked: {}", achievement.name);
+        spawn_toast(&mut commands, &theme, achievement, index);
+    }
+}
+
+fn hide_toasts(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut toast_query: Query<(Entity, &mut ToastComponent)>,
+) {
+    for (entity, mut toast) in toast_query.iter_mut() {
+        if toast.0.tick(time.delta()).finished() {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
+
+/// The toasts unlocked together are stacked by `index`
+fn spawn_toast(
+    commands: &mut Commands,
+    theme: &ThemeResource,
+    achievement: &Achievement,
+    index: usize,
+) {
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: UiRect {
+                    bottom: Val::Px(10.0 + 50.0 * index as f32),
+                    right: Val::Px(10.0),
+                    ..default()
+                },
+                padding: UiRect::all(Val::Px(8.0)),
+                ..default()
+            },
+            color: Color::rgba(0.0, 0.0, 0.0, 0.8).into(),
+            ..default()
+        })
+        .insert(ToastComponent(Timer::from_seconds(TOAST_SECONDS, false)))
+        .with_children(|parent| {
+            parent
+                .spawn_bundle(TextBundle::from_sections([
+                    TextSection::new(
+                        format!("Achievement unlocked: {}\n", achievement.name),
+                        TextStyle {
+                            color: theme.highlight_color(),
+                            ..theme.text_style(16.0)
+                        },
+                    ),
+                    TextSection::new(achievement.description.clone(), theme.text_style(12.0)),
+                ]))
+                .insert(ThemedTextComponent(vec![
+                    ThemeColor::Highlight,
+                    ThemeColor::Text,
+                ]));
+        });
+}
+
+mod resources {
+    use super::*;
+
+    pub struct AchievementsResource {
+        pub achievements: Vec<Achievement>,
+        /// Not saved without it
+        pub path: Option<PathBuf>,
+        pub unlocked: UnlockedAchievements,
+    }
+
+    /// Indexed by `SnakeId`
+    pub struct ProgressResource(pub Vec<Progress>);
+}
+
+mod components {
+    use bevy::prelude::{Component, Timer};
+
+    /// Despawned when the timer finishes
+    #[derive(Component)]
+    pub struct ToastComponent(pub Timer);
+}
@@ -0,0 +1,199 @@
+//! Goals reached once for all, defined in `assets/achievements.json`
+//! and kept between the runs next to the high scores.
+
+use std::{
+    collections::BTreeMap,
+    fs, io,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+/// The achievements of the game
+pub const ACHIEVEMENTS: &str = include_str!("../assets/achievements.json");
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Achievement {
+    /// Never changed, once released: the unlocked achievements are saved by id
+    pub id: String,
+    pub name: String,
+    pub description: String,
+    pub goal: Goal,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Goal {
+    /// Eat the foods, turning `max_turns` times at most if given
+    Foods {
+        foods: usize,
+        #[serde(default)]
+        max_turns: Option<usize>,
+    },
+    Length {
+        length: usize,
+    },
+    Survive {
+        seconds: u64,
+    },
+    /// The snake is on every cell out of the walls
+    FillBoard,
+    /// The last snake alive, if more than one played
+    Win,
+}
+
+/// What a snake did in the current game
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Progress {
+    pub foods: usize,
+    pub turns: usize,
+    pub length: usize,
+    pub survived: Duration,
+    pub won: bool,
+}
+
+impl Goal {
+    /// `open_cells` is the board to fill
+    pub fn is_reached(&self, progress: &Progress, open_cells: usize) -> bool {
+        match self {
+            Goal::Foods { foods, max_turns } => {
+                progress.foods >= *foods && max_turns.is_none_or(|max| progress.turns <= max)
+            }
+            Goal::Length { length } => progress.length >= *length,
+            Goal::Survive { seconds } => progress.survived >= Duration::from_secs(*seconds),
+            Goal::FillBoard => progress.length >= open_cells,
+            Goal::Win => progress.won,
+        }
+    }
+}
+
+pub fn parse_achievements(json: &str) -> Result<Vec<Achievement>, serde_json::Error> {
+    serde_json::from_str(json)
+}
+
+/// By achievement id, when it was unlocked: seconds since the Unix epoch
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UnlockedAchievements(BTreeMap<String, u64>);
+
+#[derive(Error, Debug)]
+pub enum AchievementsError {
+    #[error("unable to access the achievements: {0}")]
+    Io(#[from] io::Error),
+    #[error("invalid achievements: {0}")]
+    Json(#[from] serde_json::Error),
+}
+
+impl UnlockedAchievements {
+    /// Where the unlocked achievements are kept in the data directory
+    pub fn path(data_dir: &Path) -> PathBuf {
+        data_dir.join("achievements.json")
+    }
+
+    /// Empty when the file is missing
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, AchievementsError> {
+        match fs::read_to_string(path) {
+            Ok(json) => Ok(serde_json::from_str(&json)?),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), AchievementsError> {
+        let path = path.as_ref();
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    pub fn is_unlocked(&self, id: &str) -> bool {
+        self.0.contains_key(id)
+    }
+
+    /// Unlocks the achievements the progress reaches, returning the new ones
+    pub fn unlock<'a>(
+        &mut self,
+        achievements: &'a [Achievement],
+        progress: &Progress,
+        open_cells: usize,
+        date: u64,
+    ) -> Vec<&'a Achievement> {
+        let unlocked: Vec<&Achievement> = achievements
+            .iter()
+            .filter(|achievement| !self.is_unlocked(&achievement.id))
+            .filter(|achievement| achievement.goal.is_reached(progress, open_cells))
+            .collect();
+        for achievement in &unlocked {
+            self.0.insert(achievement.id.clone(), date);
+        }
+
+        unlocked
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{parse_achievements, Progress, UnlockedAchievements, ACHIEVEMENTS};
+
+    #[test]
+    fn test_achievements_should_be_unlocked_once() {
+        let achievements = parse_achievements(ACHIEVEMENTS).unwrap();
+        let mut unlocked = UnlockedAchievements::default();
+
+        let mut progress = Progress {
+            foods: 10,
+            turns: 21,
+            length: 12,
+            survived: Duration::from_secs(60),
+            won: false,
+        };
+        let ids: Vec<&str> = unlocked
+            .unlock(&achievements, &progress, 40, 1)
+            .iter()
+            .map(|achievement| achievement.id.as_str())
+            .collect();
+        assert_eq!(ids, vec!["first_bite"]);
+
+        progress.length = 40;
+        progress.won = true;
+        let ids: Vec<&str> = unlocked
+            .unlock(&achievements, &progress, 40, 2)
+            .iter()
+            .map(|achievement| achievement.id.as_str())
+            .collect();
+        assert_eq!(ids, vec!["full_board", "last_one_standing"]);
+
+        assert!(unlocked.is_unlocked("first_bite"));
+        assert!(!unlocked.is_unlocked("straight_line"));
+        assert!(unlocked.unlock(&achievements, &progress, 40, 3).is_empty());
+    }
+
+    #[test]
+    fn test_unlocked_achievements_should_be_saved() {
+        let path = std::env::temp_dir()
+            .join(format!("bevy-snake-achievements-{}", std::process::id()))
+            .join("achievements.json");
+
+        assert_eq!(
+            UnlockedAchievements::load(&path).unwrap(),
+            UnlockedAchievements::default()
+        );
+
+        let achievements = parse_achievements(ACHIEVEMENTS).unwrap();
+        let mut unlocked = UnlockedAchievements::default();
+        let progress = Progress {
+            foods: 1,
+            ..Progress::default()
+        };
+        unlocked.unlock(&achievements, &progress, 40, 1_700_000_000);
+        unlocked.save(&path).unwrap();
+        assert_eq!(UnlockedAchievements::load(&path).unwrap(), unlocked);
+
+        std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+}
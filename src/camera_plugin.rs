@@ -0,0 +1,258 @@
+use bevy::{core_pipeline::clear_color::ClearColorConfig, prelude::*, render::camera::Viewport};
+
+use crate::{
+    snake::SnakeGame,
+    snake_plugin::{
+        components::HeadSnakeComponent,
+        resources::{DrawConfigurationResource, LocalPlayersResource},
+    },
+};
+
+use self::{components::MinimapCameraComponent, events::ShakeEvent};
+
+/// How fast the camera catches up with the snake: the bigger, the closer
+const CAMERA_SPEED: f32 = 5.;
+/// The side of the minimap, in pixels
+const MINIMAP_SIZE: f32 = 100.;
+/// From the corner of the window
+const MINIMAP_PADDING: f32 = 5.;
+const SHAKE_SECONDS: f32 = 0.4;
+/// The farthest the camera is moved, in pixels
+const SHAKE_STRENGTH: f32 = 8.;
+
+/// The camera follows the first local player on the boards bigger than the window,
+/// with a minimap of the whole board in the bottom right corner. Shakes on `ShakeEvent`.
+/// Needs `SnakePlugin`
+pub struct CameraPlugin;
+
+impl Plugin for CameraPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<ShakeEvent>()
+            .add_startup_system(spawn_minimap)
+            .add_system(follow_snake.label("follow_snake").after("update_snake"))
+            .add_system(shake_camera.after("follow_snake"))
+            .add_system(fit_minimap);
+    }
+}
+
+fn spawn_minimap(mut commands: Commands) {
+    commands
+        .spawn_bundle(Camera2dBundle {
+            camera: Camera {
+                // Over the game
+                priority: 1,
+                is_active: false,
+                ..default()
+            },
+            camera_2d: Camera2d {
+                clear_color: ClearColorConfig::Custom(Color::rgba(0., 0., 0., 0.8)),
+            },
+            ..default()
+        })
+        .insert(UiCameraConfig { show_ui: false })
+        .insert(MinimapCameraComponent);
+}
+
+/// The camera of the board, not the minimap
+type MainCamera = (
+    With<Camera2d>,
+    Without<MinimapCameraComponent>,
+    Without<HeadSnakeComponent>,
+);
+
+fn follow_snake(
+    time: Res<Time>,
+    windows: Res<Windows>,
+    snake_game: Res<SnakeGame>,
+    draw_configuration: Res<DrawConfigurationResource>,
+    local_players: Res<LocalPlayersResource>,
+    head_snake_query: Query<(&Transform, &HeadSnakeComponent)>,
+    mut camera_query: Query<&mut Transform, MainCamera>,
+) {
+    let window = match windows.get_primary() {
+        None => return,
+        Some(w) => w,
+    };
+    let window = Vec2::new(window.width(), window.height());
+    let board = board_rect(&draw_configuration, snake_game.dimension());
+
+    // The spectators follow the first snake
+    let player = local_players.0.first().map_or(0, |id| id.0);
+    let head = head_snake_query
+        .iter()
+        .find(|(_, head)| head.0 == player)
+        .map(|(transform, _)| transform.translation.truncate());
+
+    for mut transform in camera_query.iter_mut() {
+        let camera = transform.translation.truncate();
+        // The dead snakes leave the camera where it is
+        let target = follow(
+            head.unwrap_or(camera),
+            window,
+            &draw_configuration.margins,
+            board,
+        );
+
+        let catch_up = 1. - (-CAMERA_SPEED * time.delta_seconds()).exp();
+        let at = camera.lerp(target, catch_up);
+        transform.translation.x = at.x;
+        transform.translation.y = at.y;
+    }
+}
+
+/// Moves the camera away from where it follows, less and less.
+/// It is back in place as it follows again
+fn shake_camera(
+    time: Res<Time>,
+    mut shake_event_reader: EventReader<ShakeEvent>,
+    mut shake: Local<Option<Timer>>,
+    mut camera_query: Query<&mut Transform, (With<Camera2d>, Without<MinimapCameraComponent>)>,
+) {
+    if shake_event_reader.iter().count() > 0 {
+        *shake = Some(Timer::from_seconds(SHAKE_SECONDS, false));
+    }
+    let timer = match shake.as_mut() {
+        None => return,
+        Some(t) => t,
+    };
+    if timer.tick(time.delta()).finished() {
+        *shake = None;
+        return;
+    }
+
+    let strength = SHAKE_STRENGTH * timer.percent_left();
+    let offset = Vec2::new(fastrand::f32() * 2. - 1., fastrand::f32() * 2. - 1.) * strength;
+    for mut transform in camera_query.iter_mut() {
+        transform.translation.x += offset.x;
+        transform.translation.y += offset.y;
+    }
+}
+
+/// Shows the whole board, only when it doesn't fit the window
+fn fit_minimap(
+    windows: Res<Windows>,
+    snake_game: Res<SnakeGame>,
+    draw_configuration: Res<DrawConfigurationResource>,
+    mut minimap_query: Query<
+        (&mut Camera, &mut OrthographicProjection, &mut Transform),
+        With<MinimapCameraComponent>,
+    >,
+) {
+    let window = match windows.get_primary() {
+        None => return,
+        Some(w) => w,
+    };
+    let (min, max) = board_rect(&draw_configuration, snake_game.dimension());
+    let board = max - min;
+    let margins = &draw_configuration.margins;
+    let room = Vec2::new(
+        window.width() - margins.left - margins.right,
+        window.height() - margins.top - margins.bottom,
+    );
+    let scrolls = board.x > room.x || board.y > room.y;
+
+    let scale_factor = window.scale_factor() as f32;
+    let size = (MINIMAP_SIZE * scale_factor) as u32;
+    let padding = (MINIMAP_PADDING * scale_factor) as u32;
+
+    for (mut camera, mut projection, mut transform) in minimap_query.iter_mut() {
+        camera.is_active = scrolls;
+        camera.viewport = Some(Viewport {
+            physical_position: UVec2::new(
+                window.physical_width().saturating_sub(size + padding),
+                window.physical_height().saturating_sub(size + padding),
+            ),
+            physical_size: UVec2::splat(size),
+            ..default()
+        });
+        projection.scale = board.max_element() / MINIMAP_SIZE;
+        let center = (min + max) / 2.;
+        transform.translation.x = center.x;
+        transform.translation.y = center.y;
+    }
+}
+
+/// The bottom left and the top right corners of the board
+fn board_rect(
+    draw_configuration: &DrawConfigurationResource,
+    dimension: (usize, usize),
+) -> (Vec2, Vec2) {
+    let min = Vec2::new(draw_configuration.origin.0, draw_configuration.origin.1);
+    let cells = Vec2::new(dimension.0 as f32, dimension.1 as f32);
+    (min, min + cells * draw_configuration.cell_size)
+}
+
+/// Where the camera puts the target in the middle of the room left by the margins,
+/// without showing past the edges of the board. Centered on a side the board fits in
+fn follow(target: Vec2, window: Vec2, margins: &UiRect<f32>, board: (Vec2, Vec2)) -> Vec2 {
+    // The room around the camera
+    let room_min = Vec2::new(
+        -window.x / 2. + margins.left,
+        -window.y / 2. + margins.bottom,
+    );
+    let room_max = Vec2::new(window.x / 2. - margins.right, window.y / 2. - margins.top);
+    let (board_min, board_max) = board;
+
+    let lowest = board_min - room_min;
+    let highest = board_max - room_max;
+    let centered = (board_min + board_max - room_min - room_max) / 2.;
+    let wanted = target - (room_min + room_max) / 2.;
+
+    let axis = |wanted: f32, lowest: f32, highest: f32, centered: f32| {
+        if lowest > highest {
+            centered
+        } else {
+            wanted.clamp(lowest, highest)
+        }
+    };
+    Vec2::new(
+        axis(wanted.x, lowest.x, highest.x, centered.x),
+        axis(wanted.y, lowest.y, highest.y, centered.y),
+    )
+}
+
+mod components {
+    use bevy::prelude::Component;
+
+    #[derive(Component)]
+    pub struct MinimapCameraComponent;
+}
+
+pub mod events {
+    pub struct ShakeEvent;
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::{math::Vec2, ui::UiRect};
+
+    use super::follow;
+
+    #[test]
+    fn test_camera_should_follow_the_target_within_the_board() {
+        let margins = UiRect {
+            top: 30.,
+            bottom: 10.,
+            right: 0.,
+            left: 0.,
+        };
+        let window = Vec2::new(100., 140.);
+        // Wider than the window, not higher than the room under the margins
+        let board = (Vec2::new(-200., -50.), Vec2::new(200., 50.));
+
+        // The room is 10 pixels under the middle of the window
+        assert_eq!(
+            follow(Vec2::new(20., 0.), window, &margins, board),
+            Vec2::new(20., 10.)
+        );
+        // Clamped at the edges
+        assert_eq!(
+            follow(Vec2::new(180., 40.), window, &margins, board),
+            Vec2::new(150., 10.)
+        );
+        assert_eq!(
+            follow(Vec2::new(-180., -40.), window, &margins, board),
+            Vec2::new(-150., 10.)
+        );
+    }
+}
@@ -1 +1,350 @@
+//! The player settings, saved as JSON between the runs.
 
+use std::{fmt, fs, io, path::Path};
+
+use bevy::{
+    input::{keyboard::KeyboardInput, ButtonState},
+    prelude::*,
+};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::snake::{Direction, Turn};
+
+/// Where the settings are read from and saved to
+pub const SETTINGS_FILE: &str = "settings.json";
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub control_scheme: ControlScheme,
+    /// The snakes slide from a cell to the next between the ticks, instead of jumping
+    pub smooth_movement: bool,
+    /// The name of the theme, the first one if unknown
+    pub theme: String,
+    /// In pixels: the big maps scroll under a camera following the snake instead of shrinking.
+    /// The whole map fits the window if missing
+    pub min_cell_size: Option<u32>,
+    /// No particles, shaking nor flashing
+    pub reduced_motion: bool,
+    pub volumes: Volumes,
+    pub key_bindings: KeyBindings,
+}
+
+#[derive(Error, Debug)]
+pub enum SettingsError {
+    #[error("unable to access the settings: {0}")]
+    Io(#[from] io::Error),
+    #[error("invalid settings: {0}")]
+    Json(#[from] serde_json::Error),
+}
+
+impl Settings {
+    /// The defaults when the file is missing
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, SettingsError> {
+        match fs::read_to_string(path) {
+            Ok(json) => Ok(serde_json::from_str(&json)?),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), SettingsError> {
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ControlScheme {
+    /// The snake goes where the pressed key points to
+    #[default]
+    Absolute,
+    /// Left and right turn the snake, up and down do nothing
+    Relative,
+}
+
+impl ControlScheme {
+    /// The turn the direction stands for, in the relative scheme
+    pub fn turn(&self, direction: Direction) -> Option<Turn> {
+        match (self, direction) {
+            (ControlScheme::Absolute, _) => None,
+            (ControlScheme::Relative, Direction::Left) => Some(Turn::Left),
+            (ControlScheme::Relative, Direction::Right) => Some(Turn::Right),
+            (ControlScheme::Relative, Direction::Up | Direction::Down) => None,
+        }
+    }
+}
+
+impl fmt::Display for ControlScheme {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ControlScheme::Absolute => write!(f, "Absolute"),
+            ControlScheme::Relative => write!(f, "Relative"),
+        }
+    }
+}
+
+/// In percent, from 0 to 100
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Volumes {
+    /// Over the music and the sounds
+    pub master: u32,
+    pub music: u32,
+    pub sounds: u32,
+}
+
+impl Volumes {
+    /// The volume to play the music at, from 0 to 1
+    pub fn music(&self) -> f32 {
+        volume(self.master) * volume(self.music)
+    }
+
+    /// The volume to play the sounds at, from 0 to 1
+    pub fn sounds(&self) -> f32 {
+        volume(self.master) * volume(self.sounds)
+    }
+}
+
+/// From 0 to 1, whatever the settings file says
+fn volume(percent: u32) -> f32 {
+    percent.min(100) as f32 / 100.
+}
+
+impl Default for Volumes {
+    /// The music under the sounds
+    fn default() -> Self {
+        Self {
+            master: 100,
+            music: 50,
+            sounds: 100,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Action {
+    /// Turns the snake of a local player
+    Steer {
+        player: usize,
+        direction: Direction,
+    },
+    Pause,
+    Restart,
+    /// Goes back a tick
+    Rewind,
+}
+
+impl fmt::Display for Action {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Action::Steer { player, direction } => write!(f, "P{} {:?}", player + 1, direction),
+            Action::Pause => write!(f, "Pause"),
+            Action::Restart => write!(f, "Restart"),
+            Action::Rewind => write!(f, "Rewind"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KeyBinding {
+    pub action: Action,
+    pub keys: Vec<KeyCode>,
+}
+
+/// The keys of each action. A key can be bound to more actions:
+/// the last binding wins, among the ones of the local players
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KeyBindings(pub Vec<KeyBinding>);
+
+impl KeyBindings {
+    /// The action of the key, ignoring the players not on this keyboard
+    pub fn action(&self, key: KeyCode, local_players: usize) -> Option<Action> {
+        self.0
+            .iter()
+            .rev()
+            .filter(|binding| binding.keys.contains(&key))
+            .map(|binding| binding.action)
+            .find(|action| match action {
+                Action::Steer { player, .. } => *player < local_players,
+                _ => true,
+            })
+    }
+
+    /// Adds the key to the binding at `index`, taking it from the conflicting ones.
+    /// The players can share a key: it steers the last one playing
+    pub fn bind(&mut self, index: usize, key: KeyCode) {
+        let action = self.0[index].action;
+        for (other_index, binding) in self.0.iter_mut().enumerate() {
+            let shared = match (action, binding.action) {
+                (Action::Steer { player, .. }, Action::Steer { player: other, .. }) => {
+                    player != other
+                }
+                _ => false,
+            };
+            if other_index != index && !shared {
+                binding.keys.retain(|k| *k != key);
+            }
+        }
+
+        let keys = &mut self.0[index].keys;
+        if !keys.contains(&key) {
+            keys.push(key);
+        }
+    }
+}
+
+impl Default for KeyBindings {
+    /// The first player steers with the arrows, HJKL or WASD,
+    /// unless the second one plays: WASD are bound to it
+    fn default() -> Self {
+        let steer = |player, direction, keys: &[KeyCode]| KeyBinding {
+            action: Action::Steer { player, direction },
+            keys: keys.to_vec(),
+        };
+
+        Self(vec![
+            steer(0, Direction::Up, &[KeyCode::Up, KeyCode::K, KeyCode::W]),
+            steer(0, Direction::Down, &[KeyCode::Down, KeyCode::J, KeyCode::S]),
+            steer(0, Direction::Left, &[KeyCode::Left, KeyCode::H, KeyCode::A]),
+            steer(
+                0,
+                Direction::Right,
+                &[KeyCode::Right, KeyCode::L, KeyCode::D],
+            ),
+            steer(1, Direction::Up, &[KeyCode::W]),
+            steer(1, Direction::Down, &[KeyCode::S]),
+            steer(1, Direction::Left, &[KeyCode::A]),
+            steer(1, Direction::Right, &[KeyCode::D]),
+            KeyBinding {
+                action: Action::Pause,
+                keys: vec![KeyCode::P, KeyCode::Space],
+            },
+            KeyBinding {
+                action: Action::Restart,
+                keys: vec![KeyCode::R],
+            },
+            KeyBinding {
+                action: Action::Rewind,
+                keys: vec![KeyCode::U, KeyCode::Back],
+            },
+        ])
+    }
+}
+
+/// The keys pressed in this frame, in order. The repeats of a held key are skipped
+pub fn just_pressed_keys(
+    keyboard_input_events: &mut EventReader<KeyboardInput>,
+    keyboard: &Input<KeyCode>,
+) -> Vec<KeyCode> {
+    keyboard_input_events
+        .iter()
+        .filter(|ki| ki.state == ButtonState::Pressed)
+        .filter_map(|ki| ki.key_code)
+        .filter(|kc| keyboard.just_pressed(*kc))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::KeyCode;
+
+    use crate::snake::Direction;
+
+    use super::{Action, ControlScheme, KeyBindings, Settings, Volumes};
+
+    #[test]
+    fn test_wasd_should_steer_the_second_player_if_any() {
+        let bindings = KeyBindings::default();
+
+        assert_eq!(
+            bindings.action(KeyCode::W, 1),
+            Some(Action::Steer {
+                player: 0,
+                direction: Direction::Up
+            })
+        );
+        assert_eq!(
+            bindings.action(KeyCode::W, 2),
+            Some(Action::Steer {
+                player: 1,
+                direction: Direction::Up
+            })
+        );
+        assert_eq!(
+            bindings.action(KeyCode::H, 2),
+            Some(Action::Steer {
+                player: 0,
+                direction: Direction::Left
+            })
+        );
+        assert_eq!(bindings.action(KeyCode::Space, 0), Some(Action::Pause));
+        assert_eq!(bindings.action(KeyCode::W, 0), None);
+        assert_eq!(bindings.action(KeyCode::Q, 1), None);
+    }
+
+    #[test]
+    fn test_rebound_key_should_leave_the_conflicting_bindings() {
+        let mut bindings = KeyBindings::default();
+        let pause = bindings
+            .0
+            .iter()
+            .position(|binding| binding.action == Action::Pause)
+            .unwrap();
+
+        bindings.bind(pause, KeyCode::W);
+        assert_eq!(bindings.action(KeyCode::W, 1), Some(Action::Pause));
+        assert_eq!(bindings.action(KeyCode::W, 2), Some(Action::Pause));
+        assert_eq!(bindings.0[0].keys, vec![KeyCode::Up, KeyCode::K]);
+
+        bindings.bind(0, KeyCode::W);
+        assert_eq!(bindings.0[pause].keys, vec![KeyCode::P, KeyCode::Space]);
+        // The second player shares it with the first one
+        bindings.bind(4, KeyCode::W);
+        let steer = |player| {
+            Some(Action::Steer {
+                player,
+                direction: Direction::Up,
+            })
+        };
+        assert_eq!(bindings.action(KeyCode::W, 1), steer(0));
+        assert_eq!(bindings.action(KeyCode::W, 2), steer(1));
+    }
+
+    #[test]
+    fn test_volumes_should_be_clamped() {
+        let volumes = Volumes {
+            master: 50,
+            music: u32::MAX,
+            sounds: 20,
+        };
+
+        assert_eq!(volumes.music(), 0.5);
+        assert_eq!(volumes.sounds(), 0.1);
+    }
+
+    #[test]
+    fn test_settings_should_be_saved() {
+        let path = std::env::temp_dir().join(format!("bevy-snake-{}.json", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        // Missing file
+        assert_eq!(Settings::load(&path).unwrap(), Settings::default());
+
+        let mut settings = Settings {
+            control_scheme: ControlScheme::Relative,
+            smooth_movement: true,
+            theme: "Night".to_string(),
+            min_cell_size: Some(24),
+            reduced_motion: true,
+            ..Settings::default()
+        };
+        settings.volumes.music = 20;
+        settings.key_bindings.0[0].keys = vec![KeyCode::I];
+        settings.save(&path).unwrap();
+        assert_eq!(Settings::load(&path).unwrap(), settings);
+
+        std::fs::remove_file(path).unwrap();
+    }
+}
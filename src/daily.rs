@@ -0,0 +1,85 @@
+//! The challenge of the day: everybody plays the same level with the same foods.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::highscores::format_date;
+
+/// The single player levels the days go through: name, level and scoring rules
+pub const DAILY_LEVELS: [(&str, &str, &str); 3] = [
+    (
+        "1",
+        include_str!("../levels/1.level"),
+        include_str!("../levels/1.scoring.json"),
+    ),
+    (
+        "2",
+        include_str!("../levels/2.level"),
+        include_str!("../levels/2.scoring.json"),
+    ),
+    (
+        "3",
+        include_str!("../levels/3.level"),
+        include_str!("../levels/3.scoring.json"),
+    ),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Daily {
+    /// Days since the Unix epoch, in UTC
+    pub day: u64,
+}
+
+impl Daily {
+    pub fn today() -> Self {
+        let seconds = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+
+        Self {
+            day: seconds / 86_400,
+        }
+    }
+
+    /// One of `DAILY_LEVELS`, a different one every day
+    pub fn level(&self) -> (&'static str, &'static str, &'static str) {
+        DAILY_LEVELS[(self.day % DAILY_LEVELS.len() as u64) as usize]
+    }
+
+    /// The seed of the foods. Close days get far apart seeds (SplitMix64)
+    pub fn seed(&self) -> u64 {
+        let mut z = self.day.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// Where the games of the day are kept in the high scores, apart from the other levels
+    pub fn high_scores_level(&self) -> String {
+        format!("daily {}", format_date(self.day * 86_400))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::snake::SnakeGame;
+
+    use super::{Daily, DAILY_LEVELS};
+
+    #[test]
+    fn test_daily_should_rotate_the_levels() {
+        for (_, level, scoring_rules) in DAILY_LEVELS {
+            let game: SnakeGame = level.parse().unwrap();
+            assert_eq!(game.snake_count(), 1);
+            serde_json::from_str::<crate::scoring::ScoringRules>(scoring_rules).unwrap();
+        }
+
+        let today = Daily { day: 20_000 };
+        let tomorrow = Daily { day: 20_001 };
+        assert_ne!(today.level().0, tomorrow.level().0);
+        assert_eq!(today.level(), Daily { day: 20_003 }.level());
+        assert_eq!(today.seed(), Daily { day: 20_000 }.seed());
+        assert_ne!(today.seed(), tomorrow.seed());
+        assert_eq!(today.high_scores_level(), "daily 2024-10-04");
+    }
+}
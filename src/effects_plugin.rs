@@ -0,0 +1,280 @@
+use std::f32::consts::TAU;
+
+use bevy::prelude::*;
+
+use crate::{
+    camera_plugin::events::ShakeEvent,
+    snake_plugin::{
+        components::{BodySnakeComponent, HeadSnakeComponent},
+        events::{FoodAteEvent, GameOverEvent},
+        move_to,
+        resources::{DrawConfigurationResource, SettingsResource, ThemeResource},
+    },
+};
+
+use self::components::{FlashComponent, ParticleComponent};
+
+// Over the snakes
+const EFFECTS_Z: f32 = 6.;
+
+const BURST_PARTICLES: usize = 12;
+const BURST_SECONDS: f32 = 0.5;
+/// In cells per second
+const BURST_SPEED: f32 = 3.;
+
+const SCATTER_SECONDS: f32 = 1.;
+/// In cells per second
+const SCATTER_SPEED: f32 = 4.;
+/// In turns per second
+const SCATTER_SPIN: f32 = 2.;
+
+const FLASH_SECONDS: f32 = 0.6;
+/// On and off in a second
+const FLASH_BLINKS: f32 = 10.;
+
+/// A burst of particles on the eaten food, the dead snakes scattering in pieces,
+/// the cell they crashed on flashing and the screen shaking.
+/// Off with the reduced motion of the settings. Needs `SnakePlugin` and `CameraPlugin`
+pub struct EffectsPlugin;
+
+impl Plugin for EffectsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(burst_on_food)
+            // The dead snakes leave the map at the end of the frame
+            .add_system(scatter_dead_snakes.after("play"))
+            .add_system(flash_collisions)
+            .add_system(move_particles)
+            .add_system(blink_flashes);
+    }
+}
+
+fn burst_on_food(
+    mut commands: Commands,
+    settings: Res<SettingsResource>,
+    theme: Res<ThemeResource>,
+    draw_configuration: Res<DrawConfigurationResource>,
+    mut food_ate_event_reader: EventReader<FoodAteEvent>,
+) {
+    for event in food_ate_event_reader.iter() {
+        if settings.settings.reduced_motion {
+            continue;
+        }
+
+        let size = draw_configuration.cell_size / 4.;
+        for direction in burst(BURST_PARTICLES) {
+            let mut transform = Transform::from_xyz(0., 0., EFFECTS_Z);
+            move_to(&mut transform, &event.position, &draw_configuration);
+            let speed = BURST_SPEED * draw_configuration.cell_size * (0.5 + fastrand::f32());
+
+            commands
+                .spawn_bundle(SpriteBundle {
+                    sprite: Sprite {
+                        color: theme.highlight_color(),
+                        custom_size: Some(Vec2::splat(size)),
+                        ..default()
+                    },
+                    transform,
+                    ..default()
+                })
+                .insert(ParticleComponent {
+                    velocity: direction * speed,
+                    spin: 0.,
+                    timer: Timer::from_seconds(BURST_SECONDS, false),
+                });
+        }
+    }
+}
+
+/// The pieces of the dead snakes fly apart, spinning
+fn scatter_dead_snakes(
+    mut commands: Commands,
+    settings: Res<SettingsResource>,
+    draw_configuration: Res<DrawConfigurationResource>,
+    mut game_over_event_reader: EventReader<GameOverEvent>,
+    head_snake_query: Query<(
+        &Transform,
+        &TextureAtlasSprite,
+        &Handle<TextureAtlas>,
+        &HeadSnakeComponent,
+    )>,
+    body_snake_query: Query<(
+        &Transform,
+        &TextureAtlasSprite,
+        &Handle<TextureAtlas>,
+        &BodySnakeComponent,
+    )>,
+) {
+    for event in game_over_event_reader.iter() {
+        if settings.settings.reduced_motion {
+            continue;
+        }
+
+        let dead = |player: usize| event.errors.iter().any(|(p, _)| *p == player);
+        let pieces = head_snake_query
+            .iter()
+            .filter(|(_, _, _, head)| dead(head.0))
+            .map(|(transform, sprite, atlas, _)| (transform, sprite, atlas))
+            .chain(
+                body_snake_query
+                    .iter()
+                    .filter(|(_, _, _, body)| dead(body.0))
+                    .map(|(transform, sprite, atlas, _)| (transform, sprite, atlas)),
+            );
+
+        for (transform, sprite, atlas) in pieces {
+            let angle = fastrand::f32() * TAU;
+            let speed = SCATTER_SPEED * draw_configuration.cell_size * (0.5 + fastrand::f32());
+            let mut transform = *transform;
+            transform.translation.z = EFFECTS_Z;
+
+            commands
+                .spawn_bundle(SpriteSheetBundle {
+                    sprite: sprite.clone(),
+                    texture_atlas: atlas.clone(),
+                    transform,
+                    ..default()
+                })
+                .insert(ParticleComponent {
+                    velocity: Vec2::new(angle.cos(), angle.sin()) * speed,
+                    spin: SCATTER_SPIN * TAU * (fastrand::f32() * 2. - 1.),
+                    timer: Timer::from_seconds(SCATTER_SECONDS, false),
+                });
+        }
+    }
+}
+
+/// Where the snakes died blinks, and the screen shakes
+fn flash_collisions(
+    mut commands: Commands,
+    settings: Res<SettingsResource>,
+    draw_configuration: Res<DrawConfigurationResource>,
+    mut game_over_event_reader: EventReader<GameOverEvent>,
+    mut shake_event_writer: EventWriter<ShakeEvent>,
+) {
+    for event in game_over_event_reader.iter() {
+        if settings.settings.reduced_motion || event.errors.is_empty() {
+            continue;
+        }
+
+        shake_event_writer.send(ShakeEvent);
+        for (_, error) in &event.errors {
+            let mut transform = Transform::from_xyz(0., 0., EFFECTS_Z);
+            move_to(&mut transform, error.position(), &draw_configuration);
+
+            commands
+                .spawn_bundle(SpriteBundle {
+                    sprite: Sprite {
+                        color: Color::WHITE,
+                        custom_size: Some(Vec2::splat(draw_configuration.cell_size)),
+                        ..default()
+                    },
+                    transform,
+                    ..default()
+                })
+                .insert(FlashComponent(Timer::from_seconds(FLASH_SECONDS, false)));
+        }
+    }
+}
+
+/// The sprites of the bursts, the scattered ones from an atlas
+type Particle<'a> = (
+    Entity,
+    &'a mut ParticleComponent,
+    &'a mut Transform,
+    Option<&'a mut Sprite>,
+    Option<&'a mut TextureAtlasSprite>,
+);
+
+/// Fading out as they go
+fn move_particles(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut particle_query: Query<Particle<'static>>,
+) {
+    for (entity, mut particle, mut transform, sprite, atlas_sprite) in particle_query.iter_mut() {
+        if particle.timer.tick(time.delta()).finished() {
+            commands.entity(entity).despawn();
+            continue;
+        }
+
+        let delta = time.delta_seconds();
+        transform.translation += (particle.velocity * delta).extend(0.);
+        transform.rotate(Quat::from_rotation_z(particle.spin * delta));
+
+        let alpha = particle.timer.percent_left();
+        if let Some(mut sprite) = sprite {
+            sprite.color.set_a(alpha);
+        }
+        if let Some(mut sprite) = atlas_sprite {
+            sprite.color.set_a(alpha);
+        }
+    }
+}
+
+fn blink_flashes(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut flash_query: Query<(Entity, &mut FlashComponent, &mut Sprite)>,
+) {
+    for (entity, mut flash, mut sprite) in flash_query.iter_mut() {
+        if flash.0.tick(time.delta()).finished() {
+            commands.entity(entity).despawn();
+            continue;
+        }
+
+        let on = ((flash.0.elapsed_secs() * FLASH_BLINKS) as usize).is_multiple_of(2);
+        sprite.color.set_a(if on { 0.9 } else { 0.2 });
+    }
+}
+
+/// The directions of the particles, evenly spread all around with a bit of chance
+fn burst(particles: usize) -> Vec<Vec2> {
+    let step = TAU / particles as f32;
+    (0..particles)
+        .map(|index| {
+            let angle = step * (index as f32 + fastrand::f32() * 0.5);
+            Vec2::new(angle.cos(), angle.sin())
+        })
+        .collect()
+}
+
+mod components {
+    use bevy::{
+        math::Vec2,
+        prelude::{Component, Timer},
+    };
+
+    #[derive(Component)]
+    pub struct ParticleComponent {
+        /// In pixels per second
+        pub velocity: Vec2,
+        /// In radians per second
+        pub spin: f32,
+        /// Gone when finished
+        pub timer: Timer,
+    }
+
+    /// Gone when finished
+    #[derive(Component)]
+    pub struct FlashComponent(pub Timer);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::burst;
+
+    #[test]
+    fn test_burst_should_spread_all_around() {
+        let directions = burst(12);
+        assert_eq!(directions.len(), 12);
+
+        for direction in &directions {
+            assert!((direction.length() - 1.).abs() < 1e-5);
+        }
+        // Some particles on each side
+        assert!(directions.iter().any(|d| d.x > 0.5));
+        assert!(directions.iter().any(|d| d.x < -0.5));
+        assert!(directions.iter().any(|d| d.y > 0.5));
+        assert!(directions.iter().any(|d| d.y < -0.5));
+    }
+}
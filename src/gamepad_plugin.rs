@@ -0,0 +1,161 @@
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+
+use crate::{
+    configuration::Action,
+    snake::Direction,
+    snake_plugin::{
+        events::{GameActionEvent, SteerEvent},
+        resources::{LocalPlayersResource, SettingsResource},
+    },
+};
+
+use self::resources::GamepadClaimsResource;
+
+/// How far the stick is pushed before turning the snake
+const STICK_DEAD_ZONE: f32 = 0.5;
+
+const DPAD: [(GamepadButtonType, Direction); 4] = [
+    (GamepadButtonType::DPadUp, Direction::Up),
+    (GamepadButtonType::DPadDown, Direction::Down),
+    (GamepadButtonType::DPadLeft, Direction::Left),
+    (GamepadButtonType::DPadRight, Direction::Right),
+];
+
+const BUTTONS: [(GamepadButtonType, Action); 2] = [
+    (GamepadButtonType::Start, Action::Pause),
+    (GamepadButtonType::Select, Action::Restart),
+];
+
+/// Steers with the D-pad or the left stick: each connected gamepad claims
+/// the first local player without one. Needs `SnakePlugin`
+pub struct GamepadPlugin;
+
+impl Plugin for GamepadPlugin {
+    fn build(&self, app: &mut App) {
+        let local_players = app.world.resource::<LocalPlayersResource>().0.len();
+
+        app.insert_resource(GamepadClaimsResource(vec![None; local_players]))
+            .add_system(claim_snakes.before("input"))
+            .add_system(gamepad_input.label("input"));
+    }
+}
+
+fn claim_snakes(
+    mut gamepad_events: EventReader<GamepadEvent>,
+    mut claims: ResMut<GamepadClaimsResource>,
+) {
+    for GamepadEvent {
+        gamepad,
+        event_type,
+    } in gamepad_events.iter()
+    {
+        match event_type {
+            GamepadEventType::Connected => {
+                match claims.0.iter_mut().find(|claim| claim.is_none()) {
+                    Some(claim) => {
+                        info!("{:?} connected", gamepad);
+                        *claim = Some(*gamepad);
+                    }
+                    None => info!("{:?} connected, but every snake is taken", gamepad),
+                }
+            }
+            GamepadEventType::Disconnected => {
+                for claim in claims
+                    .0
+                    .iter_mut()
+                    .filter(|claim| **claim == Some(*gamepad))
+                {
+                    info!("{:?} disconnected", gamepad);
+                    *claim = None;
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn gamepad_input(
+    claims: Res<GamepadClaimsResource>,
+    buttons: Res<Input<GamepadButton>>,
+    axes: Res<Axis<GamepadAxis>>,
+    settings: Res<SettingsResource>,
+    // The stick direction of the last frame: the snake turns when it changes
+    mut sticks: Local<HashMap<Gamepad, Direction>>,
+    mut steer_event_writer: EventWriter<SteerEvent>,
+    mut game_action_event_writer: EventWriter<GameActionEvent>,
+) {
+    if settings.editing() {
+        return;
+    }
+
+    let claims = claims
+        .0
+        .iter()
+        .enumerate()
+        .filter_map(|(player, claim)| claim.map(|gamepad| (player, gamepad)));
+
+    for (player, gamepad) in claims {
+        for (button_type, direction) in DPAD {
+            if buttons.just_pressed(GamepadButton::new(gamepad, button_type)) {
+                steer_event_writer.send(SteerEvent { player, direction });
+            }
+        }
+
+        let x = axes
+            .get(GamepadAxis::new(gamepad, GamepadAxisType::LeftStickX))
+            .unwrap_or_default();
+        let y = axes
+            .get(GamepadAxis::new(gamepad, GamepadAxisType::LeftStickY))
+            .unwrap_or_default();
+        match snap(x, y) {
+            Some(direction) if sticks.get(&gamepad) != Some(&direction) => {
+                sticks.insert(gamepad, direction);
+                steer_event_writer.send(SteerEvent { player, direction });
+            }
+            Some(_) => {}
+            None => {
+                sticks.remove(&gamepad);
+            }
+        }
+
+        for (button_type, action) in BUTTONS {
+            if buttons.just_pressed(GamepadButton::new(gamepad, button_type)) {
+                game_action_event_writer.send(GameActionEvent(action));
+            }
+        }
+    }
+}
+
+/// The direction the stick points to the most, if pushed out of the dead zone
+fn snap(x: f32, y: f32) -> Option<Direction> {
+    if x.abs().max(y.abs()) < STICK_DEAD_ZONE {
+        return None;
+    }
+
+    Some(Direction::dominant(x, y))
+}
+
+mod resources {
+    use bevy::input::gamepad::Gamepad;
+
+    /// The gamepad of each local player, if any
+    pub struct GamepadClaimsResource(pub Vec<Option<Gamepad>>);
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::snake::Direction;
+
+    use super::snap;
+
+    #[test]
+    fn test_stick_should_snap_to_four_directions() {
+        assert_eq!(snap(0.1, -0.3), None);
+        assert_eq!(snap(0.9, 0.2), Some(Direction::Right));
+        assert_eq!(snap(-0.6, 0.55), Some(Direction::Left));
+        assert_eq!(snap(0.3, 0.7), Some(Direction::Up));
+        assert_eq!(snap(-0.4, -0.8), Some(Direction::Down));
+    }
+}
@@ -0,0 +1,136 @@
+use std::path::PathBuf;
+
+use bevy::prelude::*;
+
+use crate::{
+    ghosts::{GhostRun, Ghosts},
+    resources::ScoreResource,
+    snake::{Position, SnakeGameSnapshot},
+    snake_plugin::{
+        events::GameOverEvent,
+        resources::{GhostResource, HistoryResource, LocalPlayersResource},
+    },
+    DataDir,
+};
+
+use self::resources::GhostsResource;
+
+/// Records the runs of the first local player. Racing, a ghost replays the best one of the level.
+/// Add it before `SnakePlugin`, playing with `GhostsResource::seed`. Needs `DataDir`
+pub struct GhostPlugin {
+    pub level: String,
+    /// The foods of the level are fixed, like the daily challenge.
+    /// Else they are new every game
+    pub seed: Option<u64>,
+    /// Shows the ghost of the best run. Without a fixed seed,
+    /// the game is played on the foods of the best run
+    pub race: bool,
+}
+
+impl Plugin for GhostPlugin {
+    fn build(&self, app: &mut App) {
+        let path = app
+            .world
+            .resource::<DataDir>()
+            .0
+            .as_deref()
+            .map(Ghosts::path);
+        let ghosts = match &path {
+            None => {
+                warn!("No data directory: the ghosts are not kept");
+                Ghosts::default()
+            }
+            Some(path) => Ghosts::load(path).unwrap_or_else(|e| {
+                warn!("Ghosts not loaded: {}", e);
+                Ghosts::default()
+            }),
+        };
+
+        // A ghost on other foods would not be a race
+        let best = ghosts
+            .best(&self.level)
+            .filter(|best| self.race && self.seed.is_none_or(|seed| seed == best.seed));
+        let seed = self.seed.or_else(|| best.map(|best| best.seed));
+        let ghost = GhostResource(best.map(|best| best.ticks.clone()).unwrap_or_default());
+
+        app.insert_resource(ghost)
+            .insert_resource(GhostsResource {
+                level: self.level.clone(),
+                path,
+                ghosts,
+                seed,
+                race: self.race,
+                recording: vec![],
+            })
+            .add_system(record_run.after("play"))
+            .add_system(keep_best_run.after("play"));
+    }
+}
+
+/// Follows the snapshot: a restart or a rewind records over the ticks taken back
+fn record_run(
+    snapshot: Res<SnakeGameSnapshot>,
+    local_players: Res<LocalPlayersResource>,
+    mut ghosts: ResMut<GhostsResource>,
+) {
+    if !snapshot.is_changed() {
+        return;
+    }
+
+    let player = local_players.0[0].0;
+    ghosts.recording.truncate(snapshot.tick);
+    ghosts.recording.push(snapshot.snakes[player].body.clone());
+}
+
+fn keep_best_run(
+    mut game_over_event_reader: EventReader<GameOverEvent>,
+    score: Res<ScoreResource>,
+    mut history: ResMut<HistoryResource>,
+    mut ghosts: ResMut<GhostsResource>,
+    mut ghost: ResMut<GhostResource>,
+) {
+    // A rewound run is not the best one
+    if game_over_event_reader.iter().count() == 0 || history.rewound {
+        return;
+    }
+
+    let ghosts = &mut *ghosts;
+    let run = GhostRun {
+        score: score.scores[0],
+        seed: history.initial.seed(),
+        ticks: ghosts.recording.clone(),
+    };
+    if !ghosts.ghosts.offer(&ghosts.level, run) {
+        return;
+    }
+
+    // The seed stays: the next games race it
+    if ghosts.race {
+        info!("New best run: the ghost replays it");
+        ghosts.seed = Some(history.initial.seed());
+        history.seed = ghosts.seed;
+        ghost.0 = ghosts.recording.clone();
+    }
+    if let Some(path) = &ghosts.path {
+        if let Err(e) = ghosts.ghosts.save(path) {
+            warn!("Unable to save the ghosts: {}", e);
+        }
+    }
+}
+
+pub mod resources {
+    use super::*;
+
+    pub struct GhostsResource {
+        pub level: String,
+        /// Not saved without it
+        pub path: Option<PathBuf>,
+        pub ghosts: Ghosts,
+        /// The seed of the foods to play with, if fixed
+        pub seed: Option<u64>,
+        /// The best run is raced on its foods
+        pub race: bool,
+        /// The body of the first local player at each tick of the current game
+        pub recording: Vec<Vec<Position>>,
+    }
+}
@@ -0,0 +1,106 @@
+//! The best run of each level, replayed by a ghost snake to race against.
+
+use std::{
+    collections::HashMap,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::snake::Position;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GhostRun {
+    pub score: usize,
+    /// The seed of the foods: the ghost only makes sense on the same ones
+    pub seed: u64,
+    /// The body of the snake at each tick, head first
+    pub ticks: Vec<Vec<Position>>,
+}
+
+/// By level name
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Ghosts(HashMap<String, GhostRun>);
+
+#[derive(Error, Debug)]
+pub enum GhostsError {
+    #[error("unable to access the ghosts: {0}")]
+    Io(#[from] io::Error),
+    #[error("invalid ghosts: {0}")]
+    Json(#[from] serde_json::Error),
+}
+
+impl Ghosts {
+    /// Where the ghosts are kept in the data directory
+    pub fn path(data_dir: &Path) -> PathBuf {
+        data_dir.join("ghosts.json")
+    }
+
+    /// Empty when the file is missing
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, GhostsError> {
+        match fs::read_to_string(path) {
+            Ok(json) => Ok(serde_json::from_str(&json)?),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), GhostsError> {
+        let path = path.as_ref();
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, serde_json::to_string(self)?)?;
+        Ok(())
+    }
+
+    pub fn best(&self, level: &str) -> Option<&GhostRun> {
+        self.0.get(level)
+    }
+
+    /// Keeps the run if it beats the best one, returning whether it was kept.
+    /// On a tie, the older run stays
+    pub fn offer(&mut self, level: &str, run: GhostRun) -> bool {
+        if self.best(level).is_some_and(|best| best.score >= run.score) {
+            return false;
+        }
+
+        self.0.insert(level.to_string(), run);
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::snake::Position;
+
+    use super::{GhostRun, Ghosts};
+
+    fn run(score: usize) -> GhostRun {
+        GhostRun {
+            score,
+            seed: 42,
+            ticks: vec![
+                vec![Position::new(1, 1), Position::new(0, 1)],
+                vec![Position::new(2, 1), Position::new(1, 1)],
+            ],
+        }
+    }
+
+    #[test]
+    fn test_ghosts_should_keep_the_best_run() {
+        let mut ghosts = Ghosts::default();
+        assert!(ghosts.best("1").is_none());
+
+        assert!(ghosts.offer("1", run(20)));
+        assert!(!ghosts.offer("1", run(20)));
+        assert!(!ghosts.offer("1", run(10)));
+        assert!(ghosts.offer("1", run(30)));
+        assert!(ghosts.offer("2", run(5)));
+
+        assert_eq!(ghosts.best("1"), Some(&run(30)));
+        assert_eq!(ghosts.best("2"), Some(&run(5)));
+    }
+}
@@ -0,0 +1,216 @@
+use std::{
+    path::PathBuf,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use bevy::prelude::*;
+
+use crate::{
+    components::GameOverTextComponent,
+    highscores::{format_date, HighScore, HighScores},
+    resources::ScoreResource,
+    snake::SnakeGameSnapshot,
+    snake_plugin::{
+        events::GameOverEvent,
+        resources::{GameClockResource, SettingsResource},
+    },
+    DataDir,
+};
+
+use self::resources::{HighScoresResource, NamePromptResource};
+
+const MAX_NAME_LENGTH: usize = 12;
+
+/// Keeps the best single player games of the level, asking the name on a new record.
+/// Needs `SnakePlugin` and `DataDir`
+pub struct HighScorePlugin {
+    pub level: String,
+}
+
+impl Plugin for HighScorePlugin {
+    fn build(&self, app: &mut App) {
+        let path = app
+            .world
+            .resource::<DataDir>()
+            .0
+            .as_deref()
+            .map(HighScores::path);
+        let high_scores = match &path {
+            None => {
+                warn!("No data directory: the high scores are not kept");
+                HighScores::default()
+            }
+            Some(path) => HighScores::load(path).unwrap_or_else(|e| {
+                warn!("High scores not loaded: {}", e);
+                HighScores::default()
+            }),
+        };
+
+        app.insert_resource(HighScoresResource {
+            level: self.level.clone(),
+            path,
+            high_scores,
+        })
+        .init_resource::<NamePromptResource>()
+        .add_system(check_record.label("check_record"))
+        .add_system(type_name.label("type_name").after("check_record"))
+        .add_system(save_record.label("save_record").after("type_name"))
+        .add_system(show_name_prompt.after("save_record"));
+    }
+}
+
+fn check_record(
+    mut game_over_event_reader: EventReader<GameOverEvent>,
+    high_scores: Res<HighScoresResource>,
+    score: Res<ScoreResource>,
+    snapshot: Res<SnakeGameSnapshot>,
+    clock: Res<GameClockResource>,
+    mut settings: ResMut<SettingsResource>,
+    mut name_prompt: ResMut<NamePromptResource>,
+) {
+    let rewound = match game_over_event_reader.iter().next() {
+        None => return,
+        Some(e) => e.rewound,
+    };
+    // Going back is not fair to the others
+    if rewound {
+        return;
+    }
+
+    let score = score.scores[0];
+    if !high_scores.high_scores.is_record(&high_scores.level, score) {
+        return;
+    }
+
+    let date = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    name_prompt.entry = Some(HighScore {
+        name: String::new(),
+        score,
+        // The snapshot stops before the deadly tick
+        length: snapshot.snakes[0].body.len(),
+        duration: clock.played,
+        date,
+    });
+    // The typed keys play no action
+    settings.editors += 1;
+}
+
+fn type_name(
+    mut received_character_events: EventReader<ReceivedCharacter>,
+    keyboard: Res<Input<KeyCode>>,
+    mut name_prompt: ResMut<NamePromptResource>,
+) {
+    // Read anyway, so the keys typed while playing are not taken
+    let characters: Vec<char> = received_character_events.iter().map(|c| c.char).collect();
+
+    let name_prompt = &mut *name_prompt;
+    let entry = match name_prompt.entry.as_mut() {
+        None => return,
+        Some(e) => e,
+    };
+
+    for c in characters.into_iter().filter(|c| !c.is_control()) {
+        if entry.name.chars().count() < MAX_NAME_LENGTH {
+            entry.name.push(c);
+        }
+    }
+    if keyboard.just_pressed(KeyCode::Back) {
+        entry.name.pop();
+    }
+    if keyboard.just_pressed(KeyCode::Return) && !entry.name.trim().is_empty() {
+        name_prompt.confirmed = true;
+    }
+}
+
+fn save_record(
+    mut name_prompt: ResMut<NamePromptResource>,
+    mut high_scores: ResMut<HighScoresResource>,
+    mut settings: ResMut<SettingsResource>,
+    mut game_over_text_query: Query<&mut Text, With<GameOverTextComponent>>,
+) {
+    if !name_prompt.confirmed {
+        return;
+    }
+    name_prompt.confirmed = false;
+    let entry = match name_prompt.entry.take() {
+        None => return,
+        Some(e) => e,
+    };
+
+    let high_scores = &mut *high_scores;
+    let rank = high_scores.high_scores.insert(&high_scores.level, entry);
+    if let Some(path) = &high_scores.path {
+        if let Err(e) = high_scores.high_scores.save(path) {
+            warn!("Unable to save the high scores: {}", e);
+        }
+    }
+    settings.editors -= 1;
+
+    let table = high_scores.high_scores.table(&high_scores.level);
+    for mut text in game_over_text_query.iter_mut() {
+        text.sections[2].value = table_text(table, rank);
+    }
+}
+
+/// In place of the table on the game over screen, till the name is confirmed
+fn show_name_prompt(
+    name_prompt: Res<NamePromptResource>,
+    mut game_over_text_query: Query<&mut Text, With<GameOverTextComponent>>,
+) {
+    let entry = match &name_prompt.entry {
+        None => return,
+        Some(e) => e,
+    };
+
+    let prompt = format!("\nNew record!\nName: {}_\nEnter to save", entry.name);
+    for mut text in game_over_text_query.iter_mut() {
+        // The screen is spawned after the prompt is asked
+        if text.sections[2].value != prompt {
+            text.sections[2].value = prompt.clone();
+        }
+    }
+}
+
+/// The rank is marked, if any
+pub fn table_text(table: &[HighScore], highlight: Option<usize>) -> String {
+    let mut text = "\nHigh scores\n".to_string();
+
+    for (rank, high_score) in table.iter().enumerate() {
+        let marker = if highlight == Some(rank) { ">" } else { " " };
+        let seconds = high_score.duration.as_secs();
+        text.push_str(&format!(
+            "{}{:>2}. {:<12} {:>3} len {:>3} {}:{:02} {}\n",
+            marker,
+            rank + 1,
+            high_score.name,
+            high_score.score,
+            high_score.length,
+            seconds / 60,
+            seconds % 60,
+            format_date(high_score.date)
+        ));
+    }
+
+    text
+}
+
+pub mod resources {
+    use super::*;
+
+    pub struct HighScoresResource {
+        pub level: String,
+        /// Not saved without it
+        pub path: Option<PathBuf>,
+        pub high_scores: HighScores,
+    }
+
+    #[derive(Default)]
+    pub struct NamePromptResource {
+        /// The record waiting for the name
+        pub entry: Option<HighScore>,
+        pub confirmed: bool,
+    }
+}
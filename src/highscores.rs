@@ -0,0 +1,202 @@
+//! The best games of each level, kept between the runs in the platform data directory.
+
+use std::{
+    collections::HashMap,
+    fs, io,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+/// The games kept for each level
+pub const MAX_HIGH_SCORES: usize = 10;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HighScore {
+    pub name: String,
+    pub score: usize,
+    /// The snake length at the end
+    pub length: usize,
+    pub duration: Duration,
+    /// Seconds since the Unix epoch
+    pub date: u64,
+}
+
+/// By level name, the best first
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HighScores(HashMap<String, Vec<HighScore>>);
+
+#[derive(Error, Debug)]
+pub enum HighScoresError {
+    #[error("unable to access the high scores: {0}")]
+    Io(#[from] io::Error),
+    #[error("invalid high scores: {0}")]
+    Json(#[from] serde_json::Error),
+}
+
+impl HighScores {
+    /// Where the high scores are kept in the data directory
+    pub fn path(data_dir: &Path) -> PathBuf {
+        data_dir.join("high_scores.json")
+    }
+
+    /// Empty when the file is missing
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, HighScoresError> {
+        match fs::read_to_string(path) {
+            Ok(json) => Ok(serde_json::from_str(&json)?),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), HighScoresError> {
+        let path = path.as_ref();
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    pub fn table(&self, level: &str) -> &[HighScore] {
+        self.0.get(level).map(Vec::as_slice).unwrap_or_default()
+    }
+
+    /// The score would enter the table
+    pub fn is_record(&self, level: &str, score: usize) -> bool {
+        let table = self.table(level);
+        score > 0 && (table.len() < MAX_HIGH_SCORES || table.iter().any(|high| high.score < score))
+    }
+
+    /// Returns the rank in the table, if the game is good enough to be kept.
+    /// On a tie, the older game stays ahead
+    pub fn insert(&mut self, level: &str, high_score: HighScore) -> Option<usize> {
+        let table = self.0.entry(level.to_string()).or_default();
+
+        let rank = table
+            .iter()
+            .position(|high| high.score < high_score.score)
+            .unwrap_or(table.len());
+        if rank >= MAX_HIGH_SCORES {
+            return None;
+        }
+
+        table.insert(rank, high_score);
+        table.truncate(MAX_HIGH_SCORES);
+        Some(rank)
+    }
+}
+
+/// `YYYY-MM-DD` of the seconds since the Unix epoch, in UTC
+pub fn format_date(seconds: u64) -> String {
+    // Howard Hinnant's days to civil date
+    let days = (seconds / 86_400) as i64 + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+/// Where the game keeps its data by default, if the platform has a data directory
+pub fn data_dir() -> Option<PathBuf> {
+    platform_data_dir().map(|dir| dir.join("bevy-snake"))
+}
+
+#[cfg(target_os = "windows")]
+fn platform_data_dir() -> Option<PathBuf> {
+    std::env::var_os("APPDATA").map(PathBuf::from)
+}
+
+#[cfg(target_os = "macos")]
+fn platform_data_dir() -> Option<PathBuf> {
+    std::env::var_os("HOME").map(|home| {
+        PathBuf::from(home)
+            .join("Library")
+            .join("Application Support")
+    })
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+fn platform_data_dir() -> Option<PathBuf> {
+    std::env::var_os("XDG_DATA_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/share")))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{format_date, HighScore, HighScores, MAX_HIGH_SCORES};
+
+    fn high_score(name: &str, score: usize) -> HighScore {
+        HighScore {
+            name: name.to_string(),
+            score,
+            length: score + 2,
+            duration: Duration::from_secs(60),
+            date: 1_700_000_000,
+        }
+    }
+
+    #[test]
+    fn test_high_scores_should_keep_the_best_ones() {
+        let mut high_scores = HighScores::default();
+        assert!(!high_scores.is_record("1", 0));
+        assert!(high_scores.is_record("1", 1));
+
+        for score in 1..=MAX_HIGH_SCORES {
+            assert_eq!(high_scores.insert("1", high_score("old", score)), Some(0));
+        }
+        assert!(!high_scores.is_record("1", 1));
+        assert!(high_scores.is_record("1", 2));
+        assert!(high_scores.is_record("versus", 1));
+
+        // Behind the older one
+        assert_eq!(high_scores.insert("1", high_score("new", 5)), Some(6));
+        assert_eq!(high_scores.insert("1", high_score("worst", 1)), None);
+
+        let table = high_scores.table("1");
+        assert_eq!(table.len(), MAX_HIGH_SCORES);
+        assert_eq!(table[0].score, MAX_HIGH_SCORES);
+        assert_eq!(table[5].name, "old");
+        assert_eq!(table[6].name, "new");
+        assert_eq!(table[MAX_HIGH_SCORES - 1].score, 2);
+    }
+
+    #[test]
+    fn test_high_scores_should_be_saved() {
+        let path = std::env::temp_dir()
+            .join(format!("bevy-snake-{}", std::process::id()))
+            .join("high_scores.json");
+
+        assert_eq!(HighScores::load(&path).unwrap(), HighScores::default());
+
+        let mut high_scores = HighScores::default();
+        high_scores.insert("1", high_score("me", 3));
+        high_scores.save(&path).unwrap();
+        assert_eq!(HighScores::load(&path).unwrap(), high_scores);
+
+        std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn test_dates_should_be_formatted() {
+        assert_eq!(format_date(0), "1970-01-01");
+        assert_eq!(format_date(951_782_400), "2000-02-29");
+        assert_eq!(format_date(1_700_000_000), "2023-11-14");
+    }
+}
@@ -0,0 +1,159 @@
+use bevy::prelude::*;
+
+use crate::{
+    highscore_plugin::resources::HighScoresResource,
+    snake::SnakeGameSnapshot,
+    snake_plugin::resources::{GameClockResource, GameTimerResource},
+};
+
+use self::{components::HudTextComponent, resources::HudResource};
+
+/// The game next to the scores: the level, the time played, the speed,
+/// the length of the snakes and the best score, if kept.
+/// The text is only written when they change. Needs `SnakePlugin`
+pub struct HudPlugin {
+    /// As shown
+    pub level: String,
+}
+
+impl Plugin for HudPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(HudResource {
+            level: self.level.clone(),
+            ..default()
+        })
+        .add_system(follow_lengths.label("follow_hud"))
+        .add_system(follow_timer.label("follow_hud"))
+        .add_system(follow_best.label("follow_hud"))
+        .add_system(update_hud_text.after("follow_hud"));
+    }
+}
+
+/// The dead snakes keep their last length
+fn follow_lengths(snapshot: Res<SnakeGameSnapshot>, mut hud: ResMut<HudResource>) {
+    if !snapshot.is_changed() {
+        return;
+    }
+
+    let lengths: Vec<usize> = snapshot
+        .snakes
+        .iter()
+        .enumerate()
+        .map(|(player, snake)| {
+            if snake.alive {
+                snake.body.len()
+            } else {
+                hud.lengths.get(player).copied().unwrap_or_default()
+            }
+        })
+        .collect();
+    if hud.lengths != lengths {
+        hud.lengths = lengths;
+    }
+}
+
+/// The time played, shown by the second
+fn follow_timer(
+    clock: Res<GameClockResource>,
+    game_timers: Res<GameTimerResource>,
+    mut hud: ResMut<HudResource>,
+) {
+    let seconds = clock.played.as_secs();
+    if hud.seconds != seconds {
+        hud.seconds = seconds;
+    }
+    let ticks_per_second = 1. / game_timers.0.duration().as_secs_f32();
+    if hud.ticks_per_second != ticks_per_second {
+        hud.ticks_per_second = ticks_per_second;
+    }
+}
+
+/// Only the single player games keep the high scores
+fn follow_best(high_scores: Option<Res<HighScoresResource>>, mut hud: ResMut<HudResource>) {
+    let high_scores = match high_scores {
+        Some(h) if h.is_changed() => h,
+        _ => return,
+    };
+
+    let best = high_scores
+        .high_scores
+        .table(&high_scores.level)
+        .first()
+        .map(|high_score| high_score.score);
+    if hud.best != best {
+        hud.best = best;
+    }
+}
+
+fn update_hud_text(
+    hud: Res<HudResource>,
+    mut hud_text_query: Query<&mut Text, With<HudTextComponent>>,
+) {
+    if !hud.is_changed() {
+        return;
+    }
+
+    for mut text in hud_text_query.iter_mut() {
+        text.sections[0].value = hud_text(&hud);
+    }
+}
+
+/// Two lines: the game, then the snakes
+fn hud_text(hud: &HudResource) -> String {
+    let lengths: Vec<String> = hud.lengths.iter().map(ToString::to_string).collect();
+    let mut text = format!(
+        "{}  {}:{:02}  {:.1}/s\nLength {}",
+        hud.level,
+        hud.seconds / 60,
+        hud.seconds % 60,
+        hud.ticks_per_second,
+        lengths.join(" | ")
+    );
+    if let Some(best) = hud.best {
+        text.push_str(&format!("  Best {}", best));
+    }
+
+    text
+}
+
+pub mod resources {
+    #[derive(Default)]
+    pub struct HudResource {
+        pub level: String,
+        /// Played, paused time aside
+        pub seconds: u64,
+        pub ticks_per_second: f32,
+        /// By player
+        pub lengths: Vec<usize>,
+        /// The high score of the level, if kept
+        pub best: Option<usize>,
+    }
+}
+
+pub mod components {
+    use bevy::prelude::Component;
+
+    #[derive(Component)]
+    pub struct HudTextComponent;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{hud_text, resources::HudResource};
+
+    #[test]
+    fn test_hud_should_show_the_game_and_the_snakes() {
+        let mut hud = HudResource {
+            level: "Level 1".to_string(),
+            seconds: 65,
+            ticks_per_second: 2.,
+            lengths: vec![4],
+            best: Some(120),
+        };
+        assert_eq!(hud_text(&hud), "Level 1  1:05  2.0/s\nLength 4  Best 120");
+
+        hud.lengths = vec![4, 6];
+        hud.best = None;
+        assert_eq!(hud_text(&hud), "Level 1  1:05  2.0/s\nLength 4 | 6");
+    }
+}
@@ -1,86 +1,537 @@
+use std::{collections::VecDeque, path::PathBuf};
+
+use achievement_plugin::AchievementPlugin;
 use bevy::{prelude::*, render::texture::ImageSettings};
-use components::ScoreTextComponent;
-use resources::ScoreResource;
+use camera_plugin::CameraPlugin;
+use components::{
+    GameOverScreenComponent, GameOverTextComponent, ScorePopupComponent, ScoreTextComponent,
+};
+use configuration::{Settings, SETTINGS_FILE};
+use daily::Daily;
+use effects_plugin::EffectsPlugin;
+use events::ScoredEvent;
+use gamepad_plugin::GamepadPlugin;
+use ghost_plugin::{resources::GhostsResource, GhostPlugin};
+use highscore_plugin::{resources::HighScoresResource, table_text, HighScorePlugin};
+use hud_plugin::{components::HudTextComponent, HudPlugin};
+use loading_plugin::LoadingPlugin;
+use network::Client;
+use remote_plugin::RemotePlugin;
+use resources::{ScoreResource, ScoringRulesResource};
+use scoring::{FoodPoints, PlayerScore, ScoreBreakdown, ScoringRules};
+use settings_plugin::SettingsPlugin;
+use snake::{GameRules, SnakeGameSnapshot, SnakeId};
 use snake_plugin::{
-    events::{FoodAteEvent, GameOverEvent},
-    SnakePlugin,
+    components::{ThemeColor, ThemedTextComponent},
+    events::{FoodAteEvent, GameOverEvent, GameTick, RestartEvent, RewindEvent, TailCutEvent},
+    move_to,
+    resources::{DrawConfigurationResource, GameTimerResource, ThemeResource},
+    SnakePlugin, REWIND_TICKS,
 };
-
-mod configuration;
+use sound_plugin::SoundPlugin;
+use spectator::{Spectator, StreamAddress};
+use spectator_plugin::{PublishPlugin, SpectatorPlugin};
+use stats_plugin::StatsPlugin;
+use swipe_plugin::SwipePlugin;
+
+mod achievement_plugin;
+pub mod achievements;
+mod camera_plugin;
+pub mod configuration;
+pub mod daily;
+mod effects_plugin;
+mod gamepad_plugin;
+mod ghost_plugin;
+pub mod ghosts;
+mod highscore_plugin;
+pub mod highscores;
+mod hud_plugin;
+mod loading_plugin;
+pub mod network;
+mod remote_plugin;
+pub mod scoring;
+mod settings_plugin;
 pub mod snake;
 mod snake_plugin;
+mod sound_plugin;
+pub mod spectator;
+mod spectator_plugin;
+pub mod stats;
+mod stats_plugin;
+mod swipe_plugin;
+pub mod themes;
+
+const SCORE_POPUP_SECONDS: f32 = 1.;
+/// In pixels per second
+const SCORE_POPUP_SPEED: f32 = 30.;
+
+/// Insert it before `MainPlugin` to choose what to play.
+/// Defaults to `GameMode::Single`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum GameMode {
+    #[default]
+    Single,
+    /// Two players on the same keyboard: arrows and WASD
+    Versus,
+    /// Against other players on a server: needs the `network::Client` resource
+    Remote,
+    /// Watches a published game: needs the `spectator::Spectator` resource
+    Spectator,
+    /// The challenge of the day, the same for everybody
+    Daily,
+}
+
+/// Insert it before `MainPlugin` to keep the high scores, the stats, the ghosts
+/// and the achievements elsewhere. Defaults to the platform data directory, if any
+pub struct DataDir(pub Option<PathBuf>);
+
+/// Insert it before `MainPlugin` to play the single player game on the foods
+/// of the best run, racing its ghost
+pub struct RaceGhost;
 
 pub struct MainPlugin;
 impl Plugin for MainPlugin {
     fn build(&self, app: &mut App) {
-        let window_description = app.world.resource::<WindowDescriptor>();
-
         let text_height = 30.;
 
         let padding = 5.;
-        // calculate the region where to put the game
-        let top = window_description.height / 2. - text_height;
-        let bottom = -window_description.height / 2. + padding;
-        let right = window_description.width / 2. - padding;
-        let left = -window_description.width / 2. + padding;
+        // The game is under the text, wherever the window is resized to
+        let margins = UiRect {
+            top: text_height,
+            bottom: padding,
+            right: padding,
+            left: padding,
+        };
+
+        let game_mode = app
+            .world
+            .get_resource::<GameMode>()
+            .copied()
+            .unwrap_or_default();
+
+        if !app.world.contains_resource::<DataDir>() {
+            app.insert_resource(DataDir(highscores::data_dir()));
+        }
+
+        // Once, or the day could change in the middle
+        let daily = Daily::today();
+
+        let (level, scoring_rules, local_players) = match game_mode {
+            GameMode::Single => (
+                include_str!("../levels/1.level").to_string(),
+                include_str!("../levels/1.scoring.json"),
+                vec![SnakeId(0)],
+            ),
+            GameMode::Versus => (
+                include_str!("../levels/versus.level").to_string(),
+                include_str!("../levels/versus.scoring.json"),
+                vec![SnakeId(0), SnakeId(1)],
+            ),
+            GameMode::Remote => {
+                let client = app.world.resource::<Client>();
+                (client.level().to_string(), "{}", vec![client.id()])
+            }
+            GameMode::Spectator => {
+                let spectator = app.world.resource::<Spectator>();
+                (spectator.level().to_string(), "{}", vec![])
+            }
+            GameMode::Daily => {
+                let (_, level, scoring_rules) = daily.level();
+                (level.to_string(), scoring_rules, vec![SnakeId(0)])
+            }
+        };
+        // Insert them to skip the ones of the level
+        let scoring_rules = match app.world.remove_resource::<ScoringRules>() {
+            Some(scoring_rules) => scoring_rules,
+            None => serde_json::from_str(scoring_rules).expect("Scoring rules should be valid"),
+        };
+
+        // The game published to the spectators, if asked
+        if let Some(address) = app.world.remove_resource::<StreamAddress>() {
+            app.add_plugin(PublishPlugin {
+                address,
+                level: level.clone(),
+            });
+        }
+
+        // Insert them to skip the settings file
+        let settings = match app.world.remove_resource::<Settings>() {
+            Some(settings) => settings,
+            None => Settings::load(SETTINGS_FILE).unwrap_or_else(|e| {
+                warn!("Default settings used: {}", e);
+                Settings::default()
+            }),
+        };
+        let remote = game_mode == GameMode::Remote || game_mode == GameMode::Spectator;
+        let level_name = match game_mode {
+            GameMode::Single => "Level 1".to_string(),
+            GameMode::Versus => "Versus".to_string(),
+            GameMode::Remote => "Online".to_string(),
+            GameMode::Spectator => "Watching".to_string(),
+            GameMode::Daily => format!("Daily {}", daily.level().0),
+        };
+
+        // The daily challenge races the ghost of the best run of the day,
+        // the other single player games only on demand
+        let ghost = match game_mode {
+            GameMode::Single => Some((
+                "1".to_string(),
+                None,
+                app.world.contains_resource::<RaceGhost>(),
+            )),
+            GameMode::Daily => Some((daily.high_scores_level(), Some(daily.seed()), true)),
+            _ => None,
+        };
+        let seed = ghost.and_then(|(level, seed, race)| {
+            app.add_plugin(GhostPlugin { level, seed, race });
+            app.world.resource::<GhostsResource>().seed
+        });
 
         app.insert_resource(ImageSettings::default_nearest())
-            .insert_resource(ScoreResource { score: 0 })
+            .insert_resource(game_mode)
+            .insert_resource(ScoringRulesResource(scoring_rules))
+            .add_event::<ScoredEvent>()
             .add_startup_system(setup(text_height))
             .add_system(show_game_over_splash)
-            .add_system(increment_score)
+            .add_system(resume_game)
+            .add_plugin(LoadingPlugin)
             .add_plugin(SnakePlugin {
-                rect: UiRect {
-                    top,
-                    left,
-                    right,
-                    bottom,
-                },
-                level: include_str!("../levels/1.level"),
-            });
+                margins,
+                level,
+                rules: GameRules::default(),
+                local_players,
+                settings,
+                remote,
+                seed,
+            })
+            .add_plugin(CameraPlugin)
+            .add_plugin(EffectsPlugin)
+            .add_plugin(GamepadPlugin)
+            .add_plugin(SwipePlugin)
+            .add_plugin(SettingsPlugin)
+            .add_plugin(SoundPlugin)
+            .add_plugin(HudPlugin { level: level_name });
+
+        match game_mode {
+            GameMode::Remote => {
+                app.add_plugin(RemotePlugin);
+            }
+            GameMode::Spectator => {
+                app.add_plugin(SpectatorPlugin);
+            }
+            GameMode::Single => {
+                app.add_plugin(HighScorePlugin {
+                    level: "1".to_string(),
+                })
+                .add_plugin(StatsPlugin {
+                    level: "1".to_string(),
+                });
+            }
+            GameMode::Versus => {
+                app.add_plugin(StatsPlugin {
+                    level: "versus".to_string(),
+                });
+            }
+            GameMode::Daily => {
+                app.add_plugin(HighScorePlugin {
+                    level: daily.high_scores_level(),
+                })
+                .add_plugin(StatsPlugin {
+                    level: daily.level().0.to_string(),
+                });
+            }
+        }
+
+        // The spectators receive the score
+        if game_mode != GameMode::Spectator {
+            app.add_system(increment_score)
+                .add_system(decrement_score)
+                .add_system(spawn_score_popups)
+                .add_system(float_score_popups);
+        }
+        if !remote {
+            app.add_system(record_score.before("play"))
+                .add_plugin(AchievementPlugin);
+        }
+
+        let players = app.world.resource::<SnakeGameSnapshot>().snakes.len();
+        app.insert_resource(ScoreResource {
+            scores: vec![0; players],
+            players: match game_mode {
+                GameMode::Spectator => vec![],
+                _ => vec![PlayerScore::default(); players],
+            },
+            history: VecDeque::new(),
+        });
     }
 }
 
 fn show_game_over_splash(
-    mut commands: Commands,
+    commands: Commands,
     mut game_over_event_reader: EventReader<GameOverEvent>,
-    asset_server: Res<AssetServer>,
+    theme: Res<ThemeResource>,
     score: Res<ScoreResource>,
-    score_component_query: Query<Entity, With<ScoreTextComponent>>,
+    high_scores: Option<Res<HighScoresResource>>,
+    mut score_component_query: Query<&mut Visibility, With<ScoreTextComponent>>,
 ) {
-    if game_over_event_reader.iter().count() == 0 {
+    let game_over_event = match game_over_event_reader.iter().last() {
+        None => return,
+        Some(e) => e,
+    };
+
+    warn!("Game over!");
+    for (player, error) in &game_over_event.errors {
+        info!("Player {} lost: {}", player + 1, error);
+    }
+
+    // Back on restart
+    for mut visibility in score_component_query.iter_mut() {
+        visibility.is_visible = false;
+    }
+
+    let mut text = match score.scores.as_slice() {
+        // Nobody died: the board is full
+        [score] if game_over_event.errors.is_empty() => {
+            format!("Board filled!\nScore: {}\n", score)
+        }
+        [score] => format!("Game over!\nScore: {}\n", score),
+        scores => {
+            let result = match game_over_event.winner {
+                Some(winner) => format!("Player {} wins!", winner + 1),
+                None => "Draw!".to_string(),
+            };
+            let scores: Vec<String> = scores
+                .iter()
+                .enumerate()
+                .map(|(player, score)| score_text(player, scores.len(), *score))
+                .collect();
+
+            format!("{}\n{}\n", result, scores.join("\n"))
+        }
+    };
+    if game_over_event.rewound {
+        text.push_str("Rewound: no record\n");
+    }
+    // The spectators receive the scores only
+    let breakdowns: Vec<String> = score
+        .players
+        .iter()
+        .enumerate()
+        .map(|(player, player_score)| {
+            breakdown_text(player, score.players.len(), &player_score.breakdown)
+        })
+        .collect();
+
+    let table = match high_scores {
+        Some(high_scores) => table_text(high_scores.high_scores.table(&high_scores.level), None),
+        None => String::new(),
+    };
+
+    spawn_game_over_screen(commands, theme, text, breakdowns.join(""), table);
+}
+
+/// The scores before each tick, to be rewound with the game
+fn record_score(mut tick_event: EventReader<GameTick>, mut score: ResMut<ScoreResource>) {
+    if tick_event.iter().count() == 0 {
         return;
     }
 
-    warn!("Game over!");
+    let players = score.players.clone();
+    score.history.push_back(players);
+    if score.history.len() > REWIND_TICKS {
+        score.history.pop_front();
+    }
+}
 
-    commands.entity(score_component_query.single()).despawn();
+/// Takes the game over screen away, if any, and the scores back
+fn resume_game(
+    mut commands: Commands,
+    mut restart_event_reader: EventReader<RestartEvent>,
+    mut rewind_event_reader: EventReader<RewindEvent>,
+    mut score: ResMut<ScoreResource>,
+    game_over_screen_query: Query<Entity, With<GameOverScreenComponent>>,
+    mut score_component_query: Query<(&mut Text, &mut Visibility, &ScoreTextComponent)>,
+) {
+    let restarted = restart_event_reader.iter().count() > 0;
+    let rewinds = rewind_event_reader.iter().count();
+    if !restarted && rewinds == 0 {
+        return;
+    }
 
-    spawn_game_over_screen(commands, asset_server, score.score);
+    if restarted {
+        score.history.clear();
+        score.players.fill(PlayerScore::default());
+    }
+    for _ in 0..rewinds {
+        if let Some(players) = score.history.pop_back() {
+            score.players = players;
+        }
+    }
+    score.scores = score.players.iter().map(PlayerScore::points).collect();
+
+    for entity in game_over_screen_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    let players = score.scores.len();
+    for (mut text, mut visibility, score_component) in score_component_query.iter_mut() {
+        let player = score_component.0;
+        text.sections[0].value = score_text(player, players, score.scores[player]);
+        visibility.is_visible = true;
+    }
 }
 
 fn increment_score(
-    mut game_over_event_reader: EventReader<FoodAteEvent>,
+    mut food_ate_event_reader: EventReader<FoodAteEvent>,
+    scoring_rules: Res<ScoringRulesResource>,
+    game_timers: Res<GameTimerResource>,
+    mut score: ResMut<ScoreResource>,
+    mut score_component_query: Query<(&mut Text, &ScoreTextComponent)>,
+    mut scored_event_writer: EventWriter<ScoredEvent>,
+) {
+    let ticks_per_second = 1. / game_timers.0.duration().as_secs_f32();
+
+    let score = &mut *score;
+    let mut changed = false;
+    for event in food_ate_event_reader.iter() {
+        let player_score = &mut score.players[event.player];
+        let points = player_score.eat(
+            &scoring_rules.0,
+            event.kind,
+            event.length,
+            ticks_per_second,
+            event.tick,
+        );
+        score.scores[event.player] = player_score.points();
+        changed = true;
+
+        scored_event_writer.send(ScoredEvent {
+            position: event.position.clone(),
+            points,
+        });
+    }
+    if !changed {
+        return;
+    }
+
+    update_score_texts(score, &mut score_component_query);
+}
+
+/// Biting the snake body costs the points of a debris for each lost segment
+fn decrement_score(
+    mut tail_cut_event_reader: EventReader<TailCutEvent>,
+    scoring_rules: Res<ScoringRulesResource>,
     mut score: ResMut<ScoreResource>,
-    mut score_component_query: Query<&mut Text, With<ScoreTextComponent>>,
+    mut score_component_query: Query<(&mut Text, &ScoreTextComponent)>,
 ) {
-    let count = game_over_event_reader.iter().count();
-    if count == 0 {
+    let score = &mut *score;
+    let mut changed = false;
+    for event in tail_cut_event_reader.iter() {
+        let player_score = &mut score.players[event.player];
+        player_score.lose(&scoring_rules.0, event.segments);
+        score.scores[event.player] = player_score.points();
+        changed = true;
+    }
+    if !changed {
         return;
     }
 
-    score.score += count;
+    update_score_texts(score, &mut score_component_query);
+}
 
-    let mut score_text = score_component_query
-        .get_single_mut()
-        .expect("Score text should exist");
-    score_text.sections[0].value = format!("Score: {}", score.score);
+fn update_score_texts(
+    score: &ScoreResource,
+    score_component_query: &mut Query<(&mut Text, &ScoreTextComponent)>,
+) {
+    let players = score.scores.len();
+    for (mut text, score_component) in score_component_query.iter_mut() {
+        let player = score_component.0;
+        text.sections[0].value = score_text(player, players, score.scores[player]);
+    }
 }
 
-fn setup(text_height: f32) -> impl Fn(Commands, Res<AssetServer>, Res<ScoreResource>) {
-    move |mut commands: Commands, asset_server: Res<AssetServer>, score: Res<ScoreResource>| {
-        let font = asset_server.load("RobotoMedium-Owv4.ttf");
+/// Over the eaten food, floating up
+fn spawn_score_popups(
+    mut commands: Commands,
+    theme: Res<ThemeResource>,
+    draw_configuration: Res<DrawConfigurationResource>,
+    mut scored_event_reader: EventReader<ScoredEvent>,
+) {
+    for event in scored_event_reader.iter() {
+        let FoodPoints { points, multiplier } = event.points;
+        let text = if multiplier > 1 {
+            format!("+{} x{}", points, multiplier)
+        } else {
+            format!("+{}", points)
+        };
+
+        let mut transform = Transform::from_xyz(0., 0., 10.);
+        move_to(&mut transform, &event.position, &draw_configuration);
+        commands
+            .spawn_bundle(Text2dBundle {
+                text: Text::from_section(
+                    text,
+                    TextStyle {
+                        color: theme.highlight_color(),
+                        ..theme.text_style(16.0)
+                    },
+                )
+                .with_alignment(TextAlignment::CENTER),
+                transform,
+                ..default()
+            })
+            .insert(ScorePopupComponent(Timer::from_seconds(
+                SCORE_POPUP_SECONDS,
+                false,
+            )))
+            .insert(ThemedTextComponent(vec![ThemeColor::Highlight]));
+    }
+}
+
+fn float_score_popups(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut popup_query: Query<(Entity, &mut ScorePopupComponent, &mut Transform)>,
+) {
+    for (entity, mut popup, mut transform) in popup_query.iter_mut() {
+        if popup.0.tick(time.delta()).finished() {
+            commands.entity(entity).despawn();
+            continue;
+        }
+        transform.translation.y += SCORE_POPUP_SPEED * time.delta_seconds();
+    }
+}
+
+fn breakdown_text(player: usize, players: usize, breakdown: &ScoreBreakdown) -> String {
+    let prefix = if players == 1 {
+        String::new()
+    } else {
+        format!("P{}: ", player + 1)
+    };
+
+    format!(
+        "{}{} foods: {} + length {} + speed {} + combo {} (best x{}) - lost {}\n",
+        prefix,
+        breakdown.foods,
+        breakdown.food,
+        breakdown.length,
+        breakdown.speed,
+        breakdown.combo,
+        breakdown.best_combo,
+        breakdown.lost
+    )
+}
+
+fn score_text(player: usize, players: usize, score: usize) -> String {
+    if players == 1 {
+        format!("Score: {}", score)
+    } else {
+        format!("P{}: {}", player + 1, score)
+    }
+}
 
+fn setup(text_height: f32) -> impl Fn(Commands, Res<ThemeResource>, Res<ScoreResource>) {
+    move |mut commands: Commands, theme: Res<ThemeResource>, score: Res<ScoreResource>| {
         // set up the camera
         let camera = Camera2dBundle::default();
         commands.spawn_bundle(camera);
@@ -89,90 +540,166 @@ fn setup(text_height: f32) -> impl Fn(Commands, Res<AssetServer>, Res<ScoreResou
         let distance_from_bottom = 5.;
         let font_size = text_height - distance_from_border_top - distance_from_bottom;
 
-        let text_style = TextStyle {
-            font,
-            font_size,
-            color: Color::WHITE,
-        };
+        let text_style = theme.text_style(font_size);
+        // Two lines in the height of the scores
+        let hud_style = theme.text_style(font_size / 2.);
 
+        // The first player on the left, the HUD, then the other players on the right
         commands
-            .spawn_bundle(
-                TextBundle::from_section(format!("Score: {}", score.score), text_style)
-                    .with_text_alignment(TextAlignment::TOP_LEFT)
-                    .with_style(Style {
-                        align_self: AlignSelf::FlexEnd,
-                        position_type: PositionType::Absolute,
-                        position: UiRect {
-                            top: Val::Px(distance_from_border_top),
-                            left: Val::Px(15.0),
-                            ..default()
-                        },
+            .spawn_bundle(NodeBundle {
+                style: Style {
+                    size: Size::new(Val::Percent(100.0), Val::Px(text_height)),
+                    position_type: PositionType::Absolute,
+                    position: UiRect {
+                        top: Val::Px(0.0),
+                        left: Val::Px(0.0),
+                        ..default()
+                    },
+                    padding: UiRect {
+                        left: Val::Px(15.0),
+                        right: Val::Px(15.0),
                         ..default()
-                    }),
-            )
-            .insert(ScoreTextComponent);
+                    },
+                    justify_content: JustifyContent::SpaceBetween,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                color: Color::NONE.into(),
+                ..default()
+            })
+            .with_children(|parent| {
+                let players = score.scores.len();
+                for (player, player_score) in score.scores.iter().enumerate() {
+                    parent
+                        .spawn_bundle(TextBundle::from_section(
+                            score_text(player, players, *player_score),
+                            text_style.clone(),
+                        ))
+                        .insert(ScoreTextComponent(player))
+                        .insert(ThemedTextComponent(vec![ThemeColor::Text]));
+
+                    if player == 0 {
+                        parent
+                            .spawn_bundle(
+                                TextBundle::from_section("", hud_style.clone())
+                                    .with_text_alignment(TextAlignment::CENTER),
+                            )
+                            .insert(HudTextComponent)
+                            .insert(ThemedTextComponent(vec![ThemeColor::Text]));
+                    }
+                }
+            });
     }
 }
 
-fn spawn_game_over_screen(mut commands: Commands, asset_server: Res<AssetServer>, score: usize) {
-    let box_size = Vec2::new(300.0, 300.0);
-    let box_position = Vec2::new(0.0, 0.0);
-
-    let font = asset_server.load("RobotoMedium-Owv4.ttf");
-    let text_style = TextStyle {
-        font,
-        font_size: 30.0,
-        color: Color::WHITE,
+/// The breakdown of the scores and the table go below the text, smaller
+fn spawn_game_over_screen(
+    mut commands: Commands,
+    theme: Res<ThemeResource>,
+    text: String,
+    breakdown: String,
+    table: String,
+) {
+    let text_style = theme.text_style(30.0);
+    let breakdown_style = TextStyle {
+        font_size: 12.0,
+        ..text_style.clone()
+    };
+    let table_style = TextStyle {
+        font_size: 10.0,
+        ..text_style.clone()
     };
 
-    commands.spawn_bundle(SpriteBundle {
-        sprite: Sprite {
-            color: Color::rgba(0.0, 0.0, 0.0, 0.975),
-            custom_size: Some(Vec2::new(box_size.x, box_size.y)),
-            ..default()
-        },
-        transform: Transform::from_translation(box_position.extend(5.0)),
-        ..default()
-    });
-
+    // The box stays in the middle of the window, whatever the camera follows
     commands
         .spawn_bundle(NodeBundle {
             style: Style {
                 size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
                 position_type: PositionType::Absolute,
                 justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
                 ..default()
             },
             color: Color::NONE.into(),
             ..default()
         })
+        .insert(GameOverScreenComponent)
         .with_children(|parent| {
-            // left vertical fill (border)
-            parent.spawn_bundle(
-                TextBundle::from_section(
-                    format!("Game over!\nScore: {}", score),
-                    text_style.clone(),
-                )
-                .with_text_alignment(TextAlignment::CENTER)
-                .with_style(Style {
-                    align_self: AlignSelf::Center,
+            parent
+                .spawn_bundle(NodeBundle {
+                    style: Style {
+                        size: Size::new(Val::Px(300.0), Val::Px(300.0)),
+                        justify_content: JustifyContent::Center,
+                        ..default()
+                    },
+                    color: Color::rgba(0.0, 0.0, 0.0, 0.975).into(),
                     ..default()
-                }),
-            );
+                })
+                .with_children(|parent| {
+                    parent
+                        .spawn_bundle(
+                            TextBundle::from_sections([
+                                TextSection::new(text, text_style),
+                                TextSection::new(breakdown, breakdown_style),
+                                TextSection::new(table, table_style),
+                            ])
+                            .with_text_alignment(TextAlignment::CENTER)
+                            .with_style(Style {
+                                align_self: AlignSelf::Center,
+                                ..default()
+                            }),
+                        )
+                        .insert(GameOverTextComponent)
+                        .insert(ThemedTextComponent(vec![ThemeColor::Text; 3]));
+                });
         });
 }
 
 mod components {
-    use bevy::prelude::Component;
+    use bevy::prelude::{Component, Timer};
+
+    /// The player the score belongs to
+    #[derive(Component)]
+    pub struct ScoreTextComponent(pub usize);
+
+    #[derive(Component)]
+    pub struct GameOverScreenComponent;
+
+    /// The text, the breakdown of the scores, then the high scores
+    #[derive(Component)]
+    pub struct GameOverTextComponent;
 
+    /// Despawned when the timer finishes
     #[derive(Component)]
-    pub struct ScoreTextComponent;
+    pub struct ScorePopupComponent(pub Timer);
+}
+
+mod events {
+    use crate::{scoring::FoodPoints, snake::Position};
+
+    /// A food has been scored
+    pub struct ScoredEvent {
+        pub position: Position,
+        pub points: FoodPoints,
+    }
 }
 
 mod resources {
+    use std::collections::VecDeque;
+
+    use crate::scoring::{PlayerScore, ScoringRules};
+
     pub struct ScoreResource {
-        pub score: usize,
+        /// Indexed by player
+        pub scores: Vec<usize>,
+        /// Indexed by player: what the scores are made of.
+        /// The spectators receive the scores only
+        pub players: Vec<PlayerScore>,
+        /// The players before each tick, the last one on the back
+        pub history: VecDeque<Vec<PlayerScore>>,
     }
+
+    pub struct ScoringRulesResource(pub ScoringRules);
 }
 
 #[cfg(test)]
@@ -180,41 +707,42 @@ mod tests {
     use bevy::{input::ButtonState, prelude::*, time::TimePlugin};
 
     use crate::{
+        loading_plugin::{resources::LoadingResource, GameState},
         snake::{Position, SnakeGame},
         snake_plugin::events::GameTick,
-        MainPlugin,
+        DataDir, MainPlugin,
     };
 
     #[test]
     fn test_e2e() {
         let mut app = create_app();
 
-        release_keyboard_key(&mut app, KeyCode::Up);
+        press_keyboard_key(&mut app, KeyCode::Up);
         run(&mut app);
 
         let world = &mut app.world;
         let game = world.resource::<SnakeGame>();
         let snapshot = game.snapshot();
         assert_eq!(
-            snapshot.snake,
+            snapshot.snakes[0].body,
             vec![Position::new(2, 3), Position::new(2, 2)]
         );
 
         // Without pressing new key, the old one is recorded
-        // release_keyboard_key(&mut app, KeyCode::Up);
+        // press_keyboard_key(&mut app, KeyCode::Up);
         run(&mut app);
 
         let world = &mut app.world;
         let game = world.resource::<SnakeGame>();
         let snapshot = game.snapshot();
         assert_eq!(
-            snapshot.snake,
+            snapshot.snakes[0].body,
             vec![Position::new(2, 4), Position::new(2, 3)]
         );
 
         info!("go to right!");
 
-        release_keyboard_key(&mut app, KeyCode::Right);
+        press_keyboard_key(&mut app, KeyCode::Right);
         run(&mut app);
         run(&mut app);
 
@@ -222,13 +750,17 @@ mod tests {
         let game = world.resource::<SnakeGame>();
         let snapshot = game.snapshot();
         assert_eq!(
-            snapshot.snake,
+            snapshot.snakes[0].body,
             vec![
                 Position::new(4, 4),
                 Position::new(3, 4),
                 Position::new(2, 4)
             ]
         );
+
+        if let Some(data_dir) = &app.world.resource::<DataDir>().0 {
+            let _ = std::fs::remove_dir_all(data_dir);
+        }
     }
 
     pub fn run(app: &mut App) {
@@ -246,7 +778,7 @@ mod tests {
         app.update();
     }
 
-    pub fn release_keyboard_key(app: &mut App, code: KeyCode) {
+    pub fn press_keyboard_key(app: &mut App, code: KeyCode) {
         use bevy::input::keyboard::KeyboardInput;
 
         let world = &mut app.world;
@@ -254,7 +786,7 @@ mod tests {
         keyboard_input.send(KeyboardInput {
             scan_code: 0,
             key_code: Some(code),
-            state: ButtonState::Released,
+            state: ButtonState::Pressed,
         });
     }
 
@@ -305,25 +837,25 @@ mod tests {
             ..default()
         });
 
-        app.add_plugin(CorePlugin::default());
-        app.add_plugin(TimePlugin::default());
-        app.add_plugin(TransformPlugin::default());
-        app.add_plugin(HierarchyPlugin::default());
-        app.add_plugin(InputPlugin::default());
+        app.add_plugin(CorePlugin);
+        app.add_plugin(TimePlugin);
+        app.add_plugin(TransformPlugin);
+        app.add_plugin(HierarchyPlugin);
+        app.add_plugin(InputPlugin);
         app.add_plugin(WindowPlugin {
             // add_primary_window: true,
             // exit_on_close: false,
         });
-        app.add_plugin(AssetPlugin::default());
+        app.add_plugin(AssetPlugin);
 
-        app.add_plugin(ScenePlugin::default());
+        app.add_plugin(ScenePlugin);
 
-        app.add_plugin(RenderPlugin::default());
-        app.add_plugin(CorePipelinePlugin::default());
-        app.add_plugin(SpritePlugin::default());
-        app.add_plugin(TextPlugin::default());
-        app.add_plugin(UiPlugin::default());
-        app.add_plugin(PbrPlugin::default());
+        app.add_plugin(RenderPlugin);
+        app.add_plugin(CorePipelinePlugin);
+        app.add_plugin(SpritePlugin);
+        app.add_plugin(TextPlugin);
+        app.add_plugin(UiPlugin);
+        app.add_plugin(PbrPlugin);
 
         {
             let camera = Camera {
@@ -333,8 +865,25 @@ mod tests {
             app.insert_resource(camera);
         }
 
+        // Away from the data of the real games
+        let data_dir = std::env::temp_dir().join(format!("bevy-snake-test-{}", std::process::id()));
+        app.insert_resource(DataDir(Some(data_dir)));
+
         app.add_plugin(MainPlugin);
 
+        // Nothing to wait for: the game starts in a few frames, whether the assets load or not
+        app.world.resource_mut::<LoadingResource>().files.clear();
+        for _ in 0..10 {
+            if *app.world.resource::<State<GameState>>().current() == GameState::Playing {
+                break;
+            }
+            app.update();
+        }
+        assert_eq!(
+            *app.world.resource::<State<GameState>>().current(),
+            GameState::Playing
+        );
+
         app
     }
 }
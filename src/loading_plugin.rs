@@ -0,0 +1,195 @@
+use bevy::{asset::LoadState, prelude::*};
+use bevy_asset_loader::{
+    dynamic_asset::{DynamicAsset, DynamicAssetType},
+    prelude::*,
+};
+
+use crate::themes::{parse_themes, theme_files, THEMES};
+
+use self::{
+    components::{ProgressBarComponent, ProgressFillComponent},
+    resources::LoadingResource,
+};
+
+const PROGRESS_BAR_SIZE: Vec2 = Vec2::new(300., 20.);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GameState {
+    Loading,
+    Playing,
+    /// An asset is missing: the game can't start
+    Failed,
+}
+
+/// The files of `LoadingResource`, known once the plugins are built
+#[derive(AssetCollection)]
+pub struct GameAssets {
+    /// Kept, so the assets stay loaded
+    #[asset(key = "files", collection)]
+    pub files: Vec<HandleUntyped>,
+}
+
+/// The files of `assets/themes.json` and the ones added to `LoadingResource`
+#[derive(Debug)]
+struct LoadingFiles(Vec<String>);
+
+impl DynamicAsset for LoadingFiles {
+    fn load(&self, asset_server: &AssetServer) -> Vec<HandleUntyped> {
+        self.0
+            .iter()
+            .map(|path| asset_server.load_untyped(path.as_str()))
+            .collect()
+    }
+
+    fn build(&self, world: &mut World) -> Result<DynamicAssetType, anyhow::Error> {
+        let asset_server = world.resource::<AssetServer>();
+        let handles = self
+            .0
+            .iter()
+            .map(|path| asset_server.get_handle_untyped(path.as_str()))
+            .collect();
+        Ok(DynamicAssetType::Collection(handles))
+    }
+}
+
+/// Waits for `GameAssets` in `GameState::Loading`, showing the progress,
+/// then goes on to `GameState::Playing`. A missing asset is reported in `GameState::Failed`.
+/// The levels are built in the game
+pub struct LoadingPlugin;
+
+impl Plugin for LoadingPlugin {
+    fn build(&self, app: &mut App) {
+        let themes = parse_themes(THEMES).expect("invalid themes");
+        let files = theme_files(&themes)
+            .into_iter()
+            .map(str::to_string)
+            .collect();
+
+        app.add_loading_state(
+            LoadingState::new(GameState::Loading)
+                .continue_to_state(GameState::Playing)
+                .with_collection::<GameAssets>(),
+        )
+        .add_state(GameState::Loading)
+        .insert_resource(LoadingResource { files })
+        // Before the loading starts, once the other plugins added their files
+        .add_startup_system(register_files)
+        .add_system_set(SystemSet::on_enter(GameState::Loading).with_system(spawn_progress_bar))
+        .add_system_set(SystemSet::on_update(GameState::Loading).with_system(track_loading))
+        .add_system_set(SystemSet::on_enter(GameState::Playing).with_system(despawn_progress_bar));
+    }
+}
+
+/// The files of `GameAssets`
+fn register_files(loading: Res<LoadingResource>, mut dynamic_assets: ResMut<DynamicAssets>) {
+    dynamic_assets.register_asset("files", Box::new(LoadingFiles(loading.files.clone())));
+}
+
+/// Drawn without the font, that could be still loading
+fn spawn_progress_bar(mut commands: Commands) {
+    commands
+        .spawn_bundle(SpriteBundle {
+            sprite: Sprite {
+                color: Color::rgb(0.2, 0.2, 0.2),
+                custom_size: Some(PROGRESS_BAR_SIZE),
+                ..default()
+            },
+            ..default()
+        })
+        .insert(ProgressBarComponent)
+        .with_children(|parent| {
+            parent
+                .spawn_bundle(SpriteBundle {
+                    sprite: Sprite {
+                        color: Color::WHITE,
+                        custom_size: Some(Vec2::new(0., PROGRESS_BAR_SIZE.y)),
+                        ..default()
+                    },
+                    transform: Transform::from_xyz(0., 0., 1.),
+                    ..default()
+                })
+                .insert(ProgressFillComponent);
+        });
+}
+
+fn track_loading(
+    asset_server: Res<AssetServer>,
+    loading: Res<LoadingResource>,
+    mut windows: ResMut<Windows>,
+    mut state: ResMut<State<GameState>>,
+    mut progress_fill_query: Query<(&mut Sprite, &mut Transform), With<ProgressFillComponent>>,
+) {
+    let load_states: Vec<LoadState> = loading
+        .files
+        .iter()
+        .map(|path| asset_server.get_load_state(path))
+        .collect();
+    let loaded = load_states
+        .iter()
+        .filter(|load_state| **load_state == LoadState::Loaded)
+        .count();
+    let missing: Vec<&str> = loading
+        .files
+        .iter()
+        .zip(&load_states)
+        .filter(|(_, load_state)| **load_state == LoadState::Failed)
+        .map(|(path, _)| path.as_str())
+        .collect();
+
+    let progress = loaded as f32 / loading.files.len().max(1) as f32;
+    for (mut sprite, mut transform) in progress_fill_query.iter_mut() {
+        let width = PROGRESS_BAR_SIZE.x * progress;
+        sprite.custom_size = Some(Vec2::new(width, PROGRESS_BAR_SIZE.y));
+        // Filled from the left
+        transform.translation.x = (width - PROGRESS_BAR_SIZE.x) / 2.;
+        if !missing.is_empty() {
+            sprite.color = Color::RED;
+        }
+    }
+
+    // `GameAssets` goes on to `GameState::Playing` once loaded
+    if missing.is_empty() {
+        return;
+    }
+
+    // The text could need a missing font
+    for path in &missing {
+        error!("Missing asset: {}", path);
+    }
+    if let Some(window) = windows.get_primary_mut() {
+        window.set_title(format!("Missing assets: {}", missing.join(", ")));
+    }
+    if let Err(e) = state.set(GameState::Failed) {
+        warn!("Unable to stop the loading: {}", e);
+    }
+}
+
+fn despawn_progress_bar(
+    mut commands: Commands,
+    game_assets: Res<GameAssets>,
+    progress_bar_query: Query<Entity, With<ProgressBarComponent>>,
+) {
+    info!("{} files loaded", game_assets.files.len());
+    for entity in progress_bar_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+pub mod resources {
+    /// The game starts when all the files are loaded.
+    /// Add them while building the plugins
+    pub struct LoadingResource {
+        /// The paths in `assets`
+        pub files: Vec<String>,
+    }
+}
+
+mod components {
+    use bevy::prelude::Component;
+
+    #[derive(Component)]
+    pub struct ProgressBarComponent;
+
+    #[derive(Component)]
+    pub struct ProgressFillComponent;
+}
@@ -1,18 +1,62 @@
 use bevy::prelude::*;
 
-use bevy_snake::{self, MainPlugin};
+use bevy_snake::{
+    self,
+    network::Client,
+    spectator::{Spectator, StreamAddress},
+    GameMode, MainPlugin, RaceGhost,
+};
 
+/// Usage: `bevy_snake [--versus | --daily | --connect <address> | --spectate <address>] [--publish <address>] [--ghost]`
+/// The spectator addresses are `<host>:<port>` or `unix:<path>`.
+/// `--ghost` races the best single player run
 fn main() {
-    App::new()
-        .insert_resource(WindowDescriptor {
-            title: "Snake at Codemotion!".to_string(),
-            width: 300.,
-            height: 300.,
-            resizable: false,
-            cursor_visible: false,
-            ..default()
-        })
-        .add_plugins(DefaultPlugins)
-        .add_plugin(MainPlugin)
-        .run();
+    let mut args = std::env::args().skip(1);
+
+    let mut app = App::new();
+    app.insert_resource(WindowDescriptor {
+        title: "Snake at Codemotion!".to_string(),
+        width: 300.,
+        height: 300.,
+        cursor_visible: false,
+        ..default()
+    });
+
+    let mut game_mode = GameMode::Single;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--versus" => game_mode = GameMode::Versus,
+            "--daily" => game_mode = GameMode::Daily,
+            "--connect" => {
+                let address = args.next().expect("Server address is missing");
+                // Blocks till the server welcomes us
+                let client = Client::connect(address).expect("Server should be reachable");
+                app.insert_resource(client);
+                game_mode = GameMode::Remote;
+            }
+            "--spectate" => {
+                let address: StreamAddress = parse_stream_address(args.next());
+                let spectator = Spectator::connect(&address).expect("Game should be published");
+                app.insert_resource(spectator);
+                game_mode = GameMode::Spectator;
+            }
+            "--publish" => {
+                app.insert_resource(parse_stream_address(args.next()));
+            }
+            "--ghost" => {
+                app.insert_resource(RaceGhost);
+            }
+            _ => panic!("Unknown argument: {}", arg),
+        }
+    }
+    app.insert_resource(game_mode);
+
+    app.add_plugins(DefaultPlugins).add_plugin(MainPlugin).run();
+}
+
+fn parse_stream_address(address: Option<String>) -> StreamAddress {
+    address
+        .expect("Stream address is missing")
+        .parse()
+        .expect("Stream address should be valid")
 }
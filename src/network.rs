@@ -0,0 +1,368 @@
+//! Multiplayer over TCP: the server plays the game,
+//! the clients send their direction and receive the snapshots.
+//! Every message is a JSON object on its own line.
+
+use std::{
+    collections::HashMap,
+    io::{self, BufRead, BufReader, Write},
+    net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs},
+    sync::{mpsc, Arc, Mutex},
+    thread,
+    time::Duration,
+};
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use tracing::{debug, info, warn};
+
+use crate::snake::{
+    Direction, GameRules, SnakeGame, SnakeGameSnapshot, SnakeId, SnakeOutcome, Turn,
+};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub enum ClientMessage {
+    Direction(Direction),
+    /// Relative to the direction of the snake on the next tick
+    Turn(Turn),
+}
+
+/// What the players asked since the last tick
+#[derive(Default)]
+struct Steering {
+    directions: HashMap<SnakeId, Direction>,
+    turns: HashMap<SnakeId, Turn>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub enum ServerMessage {
+    /// The first message: the snake the client steers and the level played
+    Welcome { id: SnakeId, level: String },
+    Tick {
+        snapshot: SnakeGameSnapshot,
+        outcomes: HashMap<SnakeId, SnakeOutcome>,
+    },
+    /// The steering of the client was received: it is played on the next tick
+    Steered,
+}
+
+pub struct Server {
+    listener: TcpListener,
+}
+
+impl Server {
+    pub fn bind(address: impl ToSocketAddrs) -> io::Result<Self> {
+        Ok(Self {
+            listener: TcpListener::bind(address)?,
+        })
+    }
+
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.listener.local_addr()
+    }
+
+    /// Waits a client for each snake in the level, then plays till the game is over.
+    /// Returns the winner, if any
+    pub fn run(self, level: &str, rules: GameRules, tick: Duration) -> io::Result<Option<SnakeId>> {
+        self.play(level, rules, || thread::sleep(tick))
+    }
+
+    /// Plays a tick whenever `wait_tick` returns
+    fn play(
+        self,
+        level: &str,
+        rules: GameRules,
+        mut wait_tick: impl FnMut(),
+    ) -> io::Result<Option<SnakeId>> {
+        let mut game = level
+            .parse::<SnakeGame>()
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "Invalid level"))?
+            .with_rules(rules);
+
+        let steering = Arc::new(Mutex::new(Steering {
+            directions: game.directions(),
+            turns: HashMap::new(),
+        }));
+
+        let mut clients = vec![];
+        for index in 0..game.snake_count() {
+            let (stream, address) = self.listener.accept()?;
+            let id = SnakeId(index);
+            info!("Player {} connected from {}", index + 1, address);
+
+            send(
+                &stream,
+                &ServerMessage::Welcome {
+                    id,
+                    level: level.to_string(),
+                },
+            )?;
+
+            let reader = BufReader::new(stream.try_clone()?);
+            // Written by the ticks and by the acknowledgements of the steering
+            let stream = Arc::new(Mutex::new(stream));
+            let writer = stream.clone();
+            let steering = steering.clone();
+            thread::spawn(move || receive_steering(id, reader, writer, steering));
+
+            clients.push(stream);
+        }
+
+        loop {
+            wait_tick();
+
+            let current_directions = {
+                let mut steering = steering.lock().unwrap();
+                let Steering { directions, turns } = &mut *steering;
+                // The turns follow where the snakes go now
+                for (id, turn) in turns.drain() {
+                    if let Some(direction) = game.direction(id) {
+                        directions.insert(id, direction.turn(turn));
+                    }
+                }
+                directions.clone()
+            };
+            let (snapshot, outcomes) = game.play_many(&current_directions);
+
+            let is_over = snapshot.is_over();
+            let winner = snapshot.winner();
+
+            let message = ServerMessage::Tick { snapshot, outcomes };
+            // A player left: the others keep playing
+            clients.retain(|client| match send(&client.lock().unwrap(), &message) {
+                Ok(_) => true,
+                Err(e) => {
+                    warn!("Client disconnected: {}", e);
+                    false
+                }
+            });
+
+            if is_over {
+                info!("Game over! Winner: {:?}", winner);
+                return Ok(winner);
+            }
+        }
+    }
+}
+
+fn receive_steering(
+    id: SnakeId,
+    mut reader: BufReader<TcpStream>,
+    writer: Arc<Mutex<TcpStream>>,
+    steering: Arc<Mutex<Steering>>,
+) {
+    loop {
+        let message = match receive(&mut reader) {
+            Ok(m) => m,
+            Err(e) => {
+                debug!("Stop receiving from {:?}: {}", id, e);
+                return;
+            }
+        };
+
+        {
+            let mut steering = steering.lock().unwrap();
+            match message {
+                ClientMessage::Direction(direction) => {
+                    steering.directions.insert(id, direction);
+                    steering.turns.remove(&id);
+                }
+                ClientMessage::Turn(turn) => {
+                    steering.turns.insert(id, turn);
+                }
+            }
+        }
+
+        if let Err(e) = send(&writer.lock().unwrap(), &ServerMessage::Steered) {
+            debug!("Stop acknowledging {:?}: {}", id, e);
+            return;
+        }
+    }
+}
+
+pub struct Client {
+    id: SnakeId,
+    level: String,
+    stream: TcpStream,
+    reader: BufReader<TcpStream>,
+}
+
+impl Client {
+    /// Connects to the server, waiting for the welcome message
+    pub fn connect(address: impl ToSocketAddrs) -> io::Result<Self> {
+        let stream = TcpStream::connect(address)?;
+        let mut reader = BufReader::new(stream.try_clone()?);
+
+        match receive(&mut reader)? {
+            ServerMessage::Welcome { id, level } => Ok(Self {
+                id,
+                level,
+                stream,
+                reader,
+            }),
+            m => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Expected welcome, got {:?}", m),
+            )),
+        }
+    }
+
+    pub fn id(&self) -> SnakeId {
+        self.id
+    }
+
+    pub fn level(&self) -> &str {
+        &self.level
+    }
+
+    pub fn send_direction(&self, direction: Direction) -> io::Result<()> {
+        send(&self.stream, &ClientMessage::Direction(direction))
+    }
+
+    pub fn send_turn(&self, turn: Turn) -> io::Result<()> {
+        send(&self.stream, &ClientMessage::Turn(turn))
+    }
+
+    /// Blocks till the next message
+    pub fn receive(&mut self) -> io::Result<ServerMessage> {
+        receive(&mut self.reader)
+    }
+
+    /// Receives the messages in background, so they can be polled
+    pub fn into_channel(mut self) -> (DirectionSender, mpsc::Receiver<ServerMessage>) {
+        let (sender, receiver) = mpsc::channel();
+        let direction_sender = DirectionSender {
+            stream: self.stream.try_clone().expect("Socket should be clonable"),
+        };
+
+        thread::spawn(move || loop {
+            let message = match self.receive() {
+                Ok(m) => m,
+                Err(e) => {
+                    warn!("Connection to the server lost: {}", e);
+                    return;
+                }
+            };
+            if sender.send(message).is_err() {
+                return;
+            }
+        });
+
+        (direction_sender, receiver)
+    }
+}
+
+pub struct DirectionSender {
+    stream: TcpStream,
+}
+
+impl DirectionSender {
+    pub fn send(&self, direction: Direction) -> io::Result<()> {
+        send(&self.stream, &ClientMessage::Direction(direction))
+    }
+
+    pub fn send_turn(&self, turn: Turn) -> io::Result<()> {
+        send(&self.stream, &ClientMessage::Turn(turn))
+    }
+}
+
+fn send(mut stream: &TcpStream, message: &impl Serialize) -> io::Result<()> {
+    let mut line = serde_json::to_string(message)?;
+    line.push('\n');
+    stream.write_all(line.as_bytes())
+}
+
+fn receive<T: DeserializeOwned>(reader: &mut impl BufRead) -> io::Result<T> {
+    let mut line = String::new();
+    if reader.read_line(&mut line)? == 0 {
+        return Err(io::ErrorKind::UnexpectedEof.into());
+    }
+    Ok(serde_json::from_str(&line)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::mpsc,
+        thread::{self, JoinHandle},
+    };
+
+    use crate::snake::{Direction, GameRules, SnakeGameSnapshot, SnakeId, Turn};
+
+    use super::{Client, Server, ServerMessage};
+
+    /// A server of the versus level, ticked by hand
+    fn start_versus() -> (String, mpsc::Sender<()>, JoinHandle<Option<SnakeId>>) {
+        let server = Server::bind("127.0.0.1:0").unwrap();
+        let address = server.local_addr().unwrap().to_string();
+        let level = include_str!("../levels/versus.level");
+
+        let (ticker, ticks) = mpsc::channel();
+        let server = thread::spawn(move || {
+            server
+                .play(level, GameRules::default(), move || {
+                    let _ = ticks.recv();
+                })
+                .unwrap()
+        });
+        (address, ticker, server)
+    }
+
+    /// Ticks once the steering is received, till the game is over
+    fn play_till_over(
+        ticker: &mpsc::Sender<()>,
+        mut clients: [&mut Client; 2],
+    ) -> SnakeGameSnapshot {
+        for client in clients.iter_mut() {
+            assert!(matches!(client.receive().unwrap(), ServerMessage::Steered));
+        }
+
+        loop {
+            ticker.send(()).unwrap();
+            let snapshots: Vec<SnakeGameSnapshot> = clients
+                .iter_mut()
+                .map(|client| match client.receive().unwrap() {
+                    ServerMessage::Tick { snapshot, .. } => snapshot,
+                    m => panic!("Unexpected message {:?}", m),
+                })
+                .collect();
+            assert_eq!(snapshots[0], snapshots[1]);
+            if snapshots[0].is_over() {
+                return snapshots[0].clone();
+            }
+        }
+    }
+
+    #[test]
+    fn test_server_with_two_clients() {
+        let (address, ticker, server) = start_versus();
+        let level = include_str!("../levels/versus.level");
+
+        let mut first = Client::connect(&address).unwrap();
+        let mut second = Client::connect(&address).unwrap();
+        assert_eq!(first.id(), SnakeId(0));
+        assert_eq!(second.id(), SnakeId(1));
+        assert_eq!(first.level(), level);
+
+        // The first snake goes straight to the wall, the second one turns away from it
+        first.send_direction(Direction::Up).unwrap();
+        second.send_direction(Direction::Left).unwrap();
+
+        let snapshot = play_till_over(&ticker, [&mut first, &mut second]);
+        assert_eq!(snapshot.winner(), Some(SnakeId(1)));
+        assert_eq!(server.join().unwrap(), Some(SnakeId(1)));
+    }
+
+    #[test]
+    fn test_server_should_turn_the_snakes() {
+        let (address, ticker, server) = start_versus();
+        let mut first = Client::connect(&address).unwrap();
+        let mut second = Client::connect(&address).unwrap();
+
+        // As before, the second snake turning on its right
+        first.send_direction(Direction::Up).unwrap();
+        second.send_turn(Turn::Right).unwrap();
+
+        let snapshot = play_till_over(&ticker, [&mut first, &mut second]);
+        assert_eq!(snapshot.winner(), Some(SnakeId(1)));
+        assert_eq!(server.join().unwrap(), Some(SnakeId(1)));
+    }
+}
@@ -0,0 +1,109 @@
+use std::sync::{mpsc::Receiver, Mutex};
+
+use bevy::prelude::*;
+
+use crate::{
+    network::{Client, DirectionSender, ServerMessage},
+    snake::{SnakeGameSnapshot, SnakeId},
+    snake_plugin::{
+        apply_tick,
+        events::GameOverEvent,
+        resources::{DirectionsResource, TurnsResource},
+    },
+};
+
+use self::resources::RemoteGameResource;
+
+/// Plays on a server: needs the `Client` resource, inserted before this plugin.
+/// `SnakePlugin` should be `remote`
+pub struct RemotePlugin;
+
+impl Plugin for RemotePlugin {
+    fn build(&self, app: &mut App) {
+        let client = app
+            .world
+            .remove_resource::<Client>()
+            .expect("Client should be connected");
+        let id = client.id();
+        let (sender, receiver) = client.into_channel();
+
+        app.insert_resource(RemoteGameResource {
+            id,
+            sender,
+            receiver: Mutex::new(receiver),
+        })
+        .add_system(send_direction.after("change_direction"))
+        .add_system(send_turns.after("change_direction"))
+        .add_system(receive_snapshots.label("play"));
+    }
+}
+
+fn send_direction(remote_game: Res<RemoteGameResource>, directions: Res<DirectionsResource>) {
+    if !directions.is_changed() {
+        return;
+    }
+
+    let direction = match directions.0.get(&remote_game.id) {
+        None => return,
+        Some(d) => *d,
+    };
+    if let Err(e) = remote_game.sender.send(direction) {
+        warn!("Unable to send the direction: {}", e);
+    }
+}
+
+/// The server turns the snake on its tick
+fn send_turns(remote_game: Res<RemoteGameResource>, mut turns: ResMut<TurnsResource>) {
+    if !turns.is_changed() {
+        return;
+    }
+
+    if let Some(turn) = turns.0.remove(&remote_game.id) {
+        if let Err(e) = remote_game.sender.send_turn(turn) {
+            warn!("Unable to send the turn: {}", e);
+        }
+    }
+}
+
+fn receive_snapshots(
+    remote_game: Res<RemoteGameResource>,
+    mut snapshot: ResMut<SnakeGameSnapshot>,
+    mut game_over_event_writer: EventWriter<GameOverEvent>,
+    mut is_over: Local<bool>,
+) {
+    if *is_over {
+        return;
+    }
+
+    let receiver = remote_game.receiver.lock().unwrap();
+    for message in receiver.try_iter() {
+        let (new_snapshot, outcomes) = match message {
+            ServerMessage::Tick { snapshot, outcomes } => (snapshot, outcomes),
+            ServerMessage::Welcome { .. } | ServerMessage::Steered => continue,
+        };
+
+        *is_over = new_snapshot.is_over();
+        apply_tick(
+            &mut snapshot,
+            new_snapshot,
+            &outcomes,
+            // The server takes no rewind
+            false,
+            &mut game_over_event_writer,
+        );
+        if *is_over {
+            return;
+        }
+    }
+}
+
+mod resources {
+    use super::*;
+
+    pub struct RemoteGameResource {
+        pub id: SnakeId,
+        pub sender: DirectionSender,
+        // Bevy resources should be `Sync`
+        pub receiver: Mutex<Receiver<ServerMessage>>,
+    }
+}
@@ -0,0 +1,194 @@
+//! How many points a food is worth: its kind, the speed of the game, the snake length
+//! and the combo of foods eaten one after the other.
+
+use serde::{Deserialize, Serialize};
+
+use crate::snake::FoodKind;
+
+/// Chosen per level, in `levels/<level>.scoring.json`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ScoringRules {
+    /// The points of the level food
+    pub food_points: usize,
+    /// The points of a severed tail left as food
+    pub debris_points: usize,
+    /// A point more for every `length_step` pieces of the snake. Zero for none
+    pub length_step: usize,
+    /// The speed the points are worth as they are: faster games are worth more
+    pub base_ticks_per_second: f32,
+    /// A food eaten within these ticks from the previous one goes on the combo
+    pub combo_ticks: usize,
+    /// The multiplier grows by one for every food in the combo, up to this
+    pub max_multiplier: usize,
+}
+
+impl Default for ScoringRules {
+    fn default() -> Self {
+        Self {
+            food_points: 10,
+            debris_points: 5,
+            length_step: 5,
+            base_ticks_per_second: 2.,
+            combo_ticks: 10,
+            max_multiplier: 5,
+        }
+    }
+}
+
+/// What a score is made of
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScoreBreakdown {
+    pub foods: usize,
+    /// The points of the foods themselves
+    pub food: usize,
+    pub length: usize,
+    pub speed: usize,
+    pub combo: usize,
+    /// The longest combo
+    pub best_combo: usize,
+    /// Taken away biting the snake body
+    pub lost: usize,
+}
+
+impl ScoreBreakdown {
+    pub fn total(&self) -> usize {
+        self.food + self.length + self.speed + self.combo - self.lost
+    }
+}
+
+/// The points of a food
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FoodPoints {
+    pub points: usize,
+    pub multiplier: usize,
+}
+
+/// The score of a player, following the combo
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PlayerScore {
+    pub breakdown: ScoreBreakdown,
+    /// The foods in the current combo
+    combo: usize,
+    last_food_tick: Option<usize>,
+}
+
+impl PlayerScore {
+    pub fn points(&self) -> usize {
+        self.breakdown.total()
+    }
+
+    /// Scores a food eaten in `tick` by a snake `length` long
+    pub fn eat(
+        &mut self,
+        rules: &ScoringRules,
+        kind: FoodKind,
+        length: usize,
+        ticks_per_second: f32,
+        tick: usize,
+    ) -> FoodPoints {
+        let in_combo = self
+            .last_food_tick
+            .is_some_and(|last| tick.saturating_sub(last) <= rules.combo_ticks);
+        self.combo = if in_combo { self.combo + 1 } else { 1 };
+        self.last_food_tick = Some(tick);
+        let multiplier = self.combo.min(rules.max_multiplier.max(1));
+
+        let food = match kind {
+            FoodKind::Food => rules.food_points,
+            FoodKind::Debris => rules.debris_points,
+        };
+        let length = length.checked_div(rules.length_step).unwrap_or_default();
+        let base = food + length;
+        // Slower games lose nothing
+        let scaled = base as f32 * ticks_per_second / rules.base_ticks_per_second;
+        let speed = (scaled.round() as usize).saturating_sub(base);
+        let points = (base + speed) * multiplier;
+
+        let breakdown = &mut self.breakdown;
+        breakdown.foods += 1;
+        breakdown.food += food;
+        breakdown.length += length;
+        breakdown.speed += speed;
+        breakdown.combo += points - base - speed;
+        breakdown.best_combo = breakdown.best_combo.max(self.combo);
+
+        FoodPoints { points, multiplier }
+    }
+
+    /// Takes the points of the lost segments away, down to zero:
+    /// each one is worth a debris
+    pub fn lose(&mut self, rules: &ScoringRules, segments: usize) {
+        let lost = segments * rules.debris_points;
+        self.breakdown.lost += lost.min(self.points());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::snake::FoodKind;
+
+    use super::{FoodPoints, PlayerScore, ScoringRules};
+
+    #[test]
+    fn test_foods_should_be_scored_in_combos() {
+        let rules = ScoringRules::default();
+        let mut score = PlayerScore::default();
+
+        // Length 4 earns no point, at the base speed
+        let points = score.eat(&rules, FoodKind::Food, 4, 2., 3);
+        assert_eq!(
+            points,
+            FoodPoints {
+                points: 10,
+                multiplier: 1
+            }
+        );
+        // Within the combo, twice as fast and longer
+        let points = score.eat(&rules, FoodKind::Debris, 10, 4., 13);
+        assert_eq!(
+            points,
+            FoodPoints {
+                points: 28,
+                multiplier: 2
+            }
+        );
+        // Too late: the combo is over
+        let points = score.eat(&rules, FoodKind::Food, 10, 2., 24);
+        assert_eq!(points.multiplier, 1);
+        assert_eq!(score.points(), 10 + 28 + 12);
+
+        score.lose(&rules, 4);
+        let breakdown = &score.breakdown;
+        assert_eq!(breakdown.foods, 3);
+        assert_eq!(breakdown.food, 25);
+        assert_eq!(breakdown.length, 4);
+        assert_eq!(breakdown.speed, 7);
+        assert_eq!(breakdown.combo, 14);
+        assert_eq!(breakdown.best_combo, 2);
+        assert_eq!(score.points(), 30);
+
+        score.lose(&rules, 100);
+        assert_eq!(score.points(), 0);
+    }
+
+    #[test]
+    fn test_lost_segments_should_cost_the_points_of_debris() {
+        let rules = ScoringRules {
+            debris_points: 3,
+            ..ScoringRules::default()
+        };
+        let mut score = PlayerScore::default();
+        score.eat(&rules, FoodKind::Food, 4, 2., 3);
+        assert_eq!(score.points(), 10);
+
+        score.lose(&rules, 2);
+        assert_eq!(score.breakdown.lost, 6);
+        assert_eq!(score.points(), 4);
+
+        // Not below zero
+        score.lose(&rules, 2);
+        assert_eq!(score.breakdown.lost, 10);
+        assert_eq!(score.points(), 0);
+    }
+}
@@ -0,0 +1,27 @@
+use std::time::Duration;
+
+use bevy_snake::{network::Server, snake::GameRules};
+use tracing::info;
+
+/// Usage: `snake_server [address] [level file]`
+fn main() {
+    tracing_subscriber::fmt::init();
+
+    let mut args = std::env::args().skip(1);
+    let address = args.next().unwrap_or_else(|| "0.0.0.0:7878".to_string());
+    let level = match args.next() {
+        Some(path) => std::fs::read_to_string(path).expect("Level should be readable"),
+        None => include_str!("../levels/versus.level").to_string(),
+    };
+
+    let server = Server::bind(&address).expect("Address should be available");
+    info!("Waiting players on {}", address);
+
+    let winner = server
+        .run(&level, GameRules::default(), Duration::from_millis(500))
+        .expect("Game should end");
+    match winner {
+        Some(winner) => info!("Player {} wins!", winner.0 + 1),
+        None => info!("Draw!"),
+    }
+}
@@ -0,0 +1,256 @@
+use bevy::{input::keyboard::KeyboardInput, prelude::*};
+
+use crate::{
+    configuration::{just_pressed_keys, ControlScheme, Settings, SETTINGS_FILE},
+    snake_plugin::{
+        components::{ThemeColor, ThemedTextComponent},
+        resources::{SettingsResource, ThemeResource},
+    },
+    themes::{find_theme, next_theme},
+};
+
+use self::{
+    components::{SettingsScreenComponent, SettingsTextComponent},
+    resources::SettingsScreenResource,
+};
+
+/// Opens and closes the settings: it can't be rebound, so nobody gets locked out
+const SETTINGS_KEY: KeyCode = KeyCode::F1;
+
+/// The minimum cell size, in pixels, when the camera follows the snake
+const FOLLOW_CELL_SIZE: u32 = 24;
+/// In percent
+const VOLUME_STEP: u32 = 10;
+
+/// The rows before the key bindings: the control scheme, the movement, the theme, the camera,
+/// the effects and the master, music and sound volumes
+const OPTION_ROWS: usize = 8;
+
+/// Chooses the options, rebinds the keys on a settings screen, saving them on close.
+/// Needs `SnakePlugin`
+pub struct SettingsPlugin;
+
+impl Plugin for SettingsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SettingsScreenResource>()
+            .add_system(rebind_keys.label("rebind_keys"))
+            .add_system(draw_settings_screen.after("rebind_keys"));
+    }
+}
+
+fn rebind_keys(
+    mut keyboard_input_events: EventReader<KeyboardInput>,
+    keyboard: Res<Input<KeyCode>>,
+    theme: Res<ThemeResource>,
+    mut settings_screen: ResMut<SettingsScreenResource>,
+    mut settings: ResMut<SettingsResource>,
+) {
+    for key in just_pressed_keys(&mut keyboard_input_events, &keyboard) {
+        if key == SETTINGS_KEY {
+            settings_screen.open = !settings_screen.open;
+            settings_screen.capturing = false;
+            if settings_screen.open {
+                settings.editors += 1;
+            } else {
+                settings.editors -= 1;
+            }
+
+            if !settings_screen.open {
+                if let Err(e) = settings.settings.save(SETTINGS_FILE) {
+                    warn!("Unable to save the settings: {}", e);
+                }
+            }
+            continue;
+        }
+
+        if !settings_screen.open {
+            continue;
+        }
+
+        let rows = settings.settings.key_bindings.0.len() + OPTION_ROWS;
+        let selected = settings_screen.selected.min(rows - 1);
+
+        let settings = &mut settings.settings;
+
+        if settings_screen.capturing {
+            settings_screen.capturing = false;
+            // Escape gives up
+            if key != KeyCode::Escape {
+                settings.key_bindings.bind(selected - OPTION_ROWS, key);
+            }
+            continue;
+        }
+
+        match key {
+            KeyCode::Up => settings_screen.selected = (selected + rows - 1) % rows,
+            KeyCode::Down => settings_screen.selected = (selected + 1) % rows,
+            KeyCode::Return if selected == 0 => {
+                settings.control_scheme = match settings.control_scheme {
+                    ControlScheme::Absolute => ControlScheme::Relative,
+                    ControlScheme::Relative => ControlScheme::Absolute,
+                };
+            }
+            KeyCode::Return if selected == 1 => {
+                settings.smooth_movement = !settings.smooth_movement
+            }
+            // Switched right away
+            KeyCode::Return if selected == 2 => {
+                settings.theme = next_theme(&theme.themes, &settings.theme).name.clone()
+            }
+            KeyCode::Return if selected == 3 => {
+                settings.min_cell_size = match settings.min_cell_size {
+                    None => Some(FOLLOW_CELL_SIZE),
+                    Some(_) => None,
+                }
+            }
+            KeyCode::Return if selected == 4 => settings.reduced_motion = !settings.reduced_motion,
+            KeyCode::Left | KeyCode::Right if (5..OPTION_ROWS).contains(&selected) => {
+                let volumes = &mut settings.volumes;
+                let volume = match selected {
+                    5 => &mut volumes.master,
+                    6 => &mut volumes.music,
+                    _ => &mut volumes.sounds,
+                };
+                *volume = match key {
+                    KeyCode::Left => volume.saturating_sub(VOLUME_STEP),
+                    _ => (*volume + VOLUME_STEP).min(100),
+                };
+            }
+            KeyCode::Return if selected >= OPTION_ROWS => settings_screen.capturing = true,
+            KeyCode::Back | KeyCode::Delete if selected >= OPTION_ROWS => {
+                settings.key_bindings.0[selected - OPTION_ROWS].keys.clear()
+            }
+            _ => {}
+        }
+    }
+}
+
+fn draw_settings_screen(
+    mut commands: Commands,
+    theme: Res<ThemeResource>,
+    settings_screen: Res<SettingsScreenResource>,
+    settings: Res<SettingsResource>,
+    settings_screen_query: Query<Entity, With<SettingsScreenComponent>>,
+    mut settings_text_query: Query<&mut Text, With<SettingsTextComponent>>,
+) {
+    if !settings_screen.is_changed() && !settings.is_changed() {
+        return;
+    }
+
+    if !settings_screen.open {
+        for entity in settings_screen_query.iter() {
+            commands.entity(entity).despawn_recursive();
+        }
+        return;
+    }
+
+    let theme_name = &find_theme(&theme.themes, &settings.settings.theme).name;
+    let text = settings_text(&settings_screen, &settings.settings, theme_name);
+    match settings_text_query.get_single_mut() {
+        Ok(mut settings_text) => settings_text.sections[0].value = text,
+        Err(_) => spawn_settings_screen(&mut commands, &theme, text),
+    }
+}
+
+fn settings_text(
+    settings_screen: &SettingsScreenResource,
+    settings: &Settings,
+    theme_name: &str,
+) -> String {
+    let mut text = "F1: close, Enter: change, Left/Right: volume, Delete: clear\n\n".to_string();
+
+    let marker = |row| {
+        if row == settings_screen.selected {
+            "> "
+        } else {
+            "  "
+        }
+    };
+    text.push_str(&format!(
+        "{}Controls: {}\n",
+        marker(0),
+        settings.control_scheme
+    ));
+    let movement = if settings.smooth_movement {
+        "Smooth"
+    } else {
+        "Stepped"
+    };
+    text.push_str(&format!("{}Movement: {}\n", marker(1), movement));
+    text.push_str(&format!("{}Theme: {}\n", marker(2), theme_name));
+    let camera = match settings.min_cell_size {
+        None => "Fit".to_string(),
+        Some(size) => format!("Follow ({}px cells)", size),
+    };
+    text.push_str(&format!("{}Camera: {}\n", marker(3), camera));
+    let effects = if settings.reduced_motion { "Off" } else { "On" };
+    text.push_str(&format!("{}Effects: {}\n", marker(4), effects));
+    let volumes = &settings.volumes;
+    text.push_str(&format!("{}Volume: {}%\n", marker(5), volumes.master));
+    text.push_str(&format!("{}Music: {}%\n", marker(6), volumes.music));
+    text.push_str(&format!("{}Sounds: {}%\n", marker(7), volumes.sounds));
+
+    for (index, binding) in settings.key_bindings.0.iter().enumerate() {
+        let row = index + OPTION_ROWS;
+        let selected = row == settings_screen.selected;
+        let keys = if selected && settings_screen.capturing {
+            "press a key...".to_string()
+        } else {
+            let keys: Vec<String> = binding.keys.iter().map(|k| format!("{:?}", k)).collect();
+            keys.join(", ")
+        };
+
+        text.push_str(&format!("{}{}: {}\n", marker(row), binding.action, keys));
+    }
+
+    text
+}
+
+fn spawn_settings_screen(commands: &mut Commands, theme: &ThemeResource, text: String) {
+    let text_style = theme.text_style(14.0);
+
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                position_type: PositionType::Absolute,
+                justify_content: JustifyContent::Center,
+                ..default()
+            },
+            color: Color::rgba(0.0, 0.0, 0.0, 0.95).into(),
+            ..default()
+        })
+        .insert(SettingsScreenComponent)
+        .with_children(|parent| {
+            parent
+                .spawn_bundle(
+                    TextBundle::from_section(text, text_style).with_style(Style {
+                        align_self: AlignSelf::Center,
+                        ..default()
+                    }),
+                )
+                .insert(SettingsTextComponent)
+                .insert(ThemedTextComponent(vec![ThemeColor::Text]));
+        });
+}
+
+pub mod resources {
+    #[derive(Default)]
+    pub struct SettingsScreenResource {
+        pub open: bool,
+        /// One of the options, or the row of the binding in the key bindings plus the options
+        pub selected: usize,
+        /// The next key is added to the selected binding
+        pub capturing: bool,
+    }
+}
+
+mod components {
+    use bevy::prelude::Component;
+
+    #[derive(Component)]
+    pub struct SettingsScreenComponent;
+
+    #[derive(Component)]
+    pub struct SettingsTextComponent;
+}
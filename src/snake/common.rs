@@ -1,10 +1,21 @@
+use serde::{Deserialize, Serialize};
+
 #[derive(Debug, Clone)]
 pub enum CellField {
     Empty,
     Wall,
 }
 
-#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+/// What a snake can eat
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FoodKind {
+    /// The food of the level, always one on the map
+    Food,
+    /// A severed tail left as food
+    Debris,
+}
+
+#[derive(Clone, Debug, Hash, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Position {
     pub x: usize,
     pub y: usize,
@@ -16,7 +27,20 @@ impl Position {
     }
 }
 
-#[derive(Debug, Clone, Copy)]
+/// The snake index in the level
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct SnakeId(pub usize);
+
+/// A rotation of the heading
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Turn {
+    /// Counter-clockwise
+    Left,
+    /// Clockwise
+    Right,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum Direction {
     Up,
     Left,
@@ -34,4 +58,48 @@ impl Direction {
                 | (Direction::Down, Direction::Up)
         )
     }
+
+    pub fn opposite(&self) -> Self {
+        match self {
+            Direction::Up => Direction::Down,
+            Direction::Down => Direction::Up,
+            Direction::Left => Direction::Right,
+            Direction::Right => Direction::Left,
+        }
+    }
+
+    /// Where the vector points to the most, with `y` growing upward
+    pub fn dominant(x: f32, y: f32) -> Self {
+        if x.abs() > y.abs() {
+            if x > 0. {
+                Direction::Right
+            } else {
+                Direction::Left
+            }
+        } else if y > 0. {
+            Direction::Up
+        } else {
+            Direction::Down
+        }
+    }
+
+    /// The heading after turning
+    pub fn turn(&self, turn: Turn) -> Self {
+        match (self, turn) {
+            (Direction::Up, Turn::Left) | (Direction::Down, Turn::Right) => Direction::Left,
+            (Direction::Up, Turn::Right) | (Direction::Down, Turn::Left) => Direction::Right,
+            (Direction::Left, Turn::Left) | (Direction::Right, Turn::Right) => Direction::Down,
+            (Direction::Left, Turn::Right) | (Direction::Right, Turn::Left) => Direction::Up,
+        }
+    }
+
+    /// The movement on the map: `Up` increments `y`
+    pub fn delta(&self) -> (isize, isize) {
+        match self {
+            Direction::Up => (0, 1),
+            Direction::Down => (0, -1),
+            Direction::Left => (-1, 0),
+            Direction::Right => (1, 0),
+        }
+    }
 }
@@ -1,18 +1,66 @@
-use std::{ops::Deref, str::FromStr};
+use std::{
+    collections::HashMap,
+    ops::{Deref, Range},
+    str::FromStr,
+};
 
-use super::common::{CellField, Direction, Position};
+use super::{
+    common::{CellField, Direction, FoodKind, Position, SnakeId},
+    rules::{GameRules, Reversal, SelfCollision, SeveredTail, WallCollision},
+    stats::SnakeStats,
+};
+use serde::{Deserialize, Serialize};
 use thiserror::Error;
 use tracing::{debug, info};
 
-#[derive(Error, Debug, PartialEq, Eq)]
+#[derive(Error, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum SnakeError {
     #[error("Snake is on the wall at {0:?}")]
     OnWall(Position),
     #[error("Snake is eating itself as {0:?}")]
     OnSnake(Position),
+    #[error("Snake turned back at {0:?}")]
+    Reversed(Position),
+    #[error("Snake is on snake {0:?} at {1:?}")]
+    OnOtherSnake(SnakeId, Position),
+    #[error("Snakes {0:?} crashed head-on at {1:?}")]
+    HeadOn(Vec<SnakeId>, Position),
 }
 
-#[derive(Debug)]
+impl SnakeError {
+    /// Where the snake died
+    pub fn position(&self) -> &Position {
+        match self {
+            SnakeError::OnWall(p)
+            | SnakeError::OnSnake(p)
+            | SnakeError::Reversed(p)
+            | SnakeError::OnOtherSnake(_, p)
+            | SnakeError::HeadOn(_, p) => p,
+        }
+    }
+
+    /// What killed the snake, without where
+    pub fn cause(&self) -> &'static str {
+        match self {
+            SnakeError::OnWall(_) => "wall",
+            SnakeError::OnSnake(_) => "itself",
+            SnakeError::Reversed(_) => "reversal",
+            SnakeError::OnOtherSnake(_, _) => "other snake",
+            SnakeError::HeadOn(_, _) => "head-on",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SnakeOutcome {
+    Alive,
+    /// Died in this tick
+    Died(SnakeError),
+    /// Died in a previous tick
+    Dead,
+}
+
+#[derive(Debug, Clone)]
 struct Row {
     cells: Vec<CellField>,
 }
@@ -24,7 +72,7 @@ impl Deref for Row {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 struct Map {
     rows: Vec<Row>,
     dimension: (usize, usize),
@@ -37,134 +85,590 @@ impl Deref for Map {
     }
 }
 
-#[derive(Debug)]
+/// Something left on the map for a while
+#[derive(Debug, Clone)]
+struct Debris {
+    position: Position,
+    ticks_left: usize,
+}
+
+#[derive(Debug, Clone)]
+struct Snake {
+    head: Position,
+    body: Vec<Position>,
+    direction: Direction,
+    increment_size: usize,
+    alive: bool,
+}
+
+impl Snake {
+    /// The pieces from the head: the snake goes from its neck to its head,
+    /// so the levels choose where each snake starts going. Up when it is just a head
+    fn new(mut pieces: Vec<Position>, dimension: (usize, usize)) -> Self {
+        let head = pieces.remove(0);
+        let direction = match pieces.first() {
+            None => Direction::Up,
+            Some(neck) => direction_between(neck, &head, dimension),
+        };
+
+        Self {
+            head,
+            body: pieces,
+            direction,
+            increment_size: 0,
+            alive: true,
+        }
+    }
+
+    fn contains(&self, position: &Position) -> bool {
+        &self.head == position || self.body.contains(position)
+    }
+
+    fn body_index(&self, position: &Position) -> Option<usize> {
+        self.body.iter().position(|p| p == position)
+    }
+
+    fn pieces(&self) -> Vec<Position> {
+        let mut pieces = self.body.clone();
+        pieces.insert(0, self.head.clone());
+        pieces
+    }
+
+    fn move_body(&mut self) {
+        if self.increment_size > 0 {
+            let new_piece = self.head.clone();
+            self.body.insert(0, new_piece);
+            self.increment_size -= 1;
+        } else {
+            let mut tail = match self.body.pop() {
+                // Means the snake is just its head, so nothing to do
+                None => return,
+                Some(p) => p,
+            };
+
+            tail.x = self.head.x;
+            tail.y = self.head.y;
+            self.body.insert(0, tail);
+        }
+    }
+
+    /// Swaps head and tail, returning the direction the new head points to
+    fn flip(&mut self, dimension: (usize, usize)) -> Direction {
+        let tail = match self.body.pop() {
+            // Means the snake is just its head: it can only turn back
+            None => return self.direction.opposite(),
+            Some(p) => p,
+        };
+
+        let old_head = std::mem::replace(&mut self.head, tail);
+        self.body.reverse();
+        self.body.push(old_head);
+
+        direction_between(&self.body[0], &self.head, dimension)
+    }
+}
+
+/// Where the next foods go: the state of the generator, so the game can be shared
+/// between threads. A clone generates the same foods, so a game played again goes the same way
+#[derive(Debug, Clone)]
+struct FoodRng(u64);
+
+impl FoodRng {
+    fn usize(&mut self, range: Range<usize>) -> usize {
+        let rng = fastrand::Rng::with_seed(self.0);
+        let value = rng.usize(range);
+        self.0 = rng.get_seed();
+        value
+    }
+}
+
+#[derive(Debug, Clone)]
 pub struct SnakeGame {
     map: Map,
-    snake_head: Position,
-    snake_body: Vec<Position>,
+    snakes: Vec<Snake>,
     food: Position,
-    direction: Direction,
-    increment_size: usize,
+    rules: GameRules,
+    temporary_walls: Vec<Debris>,
+    temporary_food: Vec<Debris>,
+    /// Indexed by `SnakeId`
+    stats: Vec<SnakeStats>,
+    /// The ticks played
+    ticks: usize,
+    food_rng: FoodRng,
+    /// No cell was left for the food
+    board_full: bool,
 }
 
 impl SnakeGame {
-    pub fn play(&mut self, mut direction: Direction) -> Result<SnakeGameSnapshot, SnakeError> {
-        info!("play with {:?}", direction);
+    /// The foods follow the seed: the same moves play the same game
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.food_rng.0 = seed;
+        self
+    }
 
-        // if the given direction is not allowed we ignore it
-        if !self.direction.allows(&direction) {
-            direction = self.direction;
+    /// The state of the foods generator: the seed, before the first food is generated
+    pub fn seed(&self) -> u64 {
+        self.food_rng.0
+    }
+
+    /// Replaces the rules the game follows.
+    /// Should be called before the first `play`
+    pub fn with_rules(mut self, rules: GameRules) -> Self {
+        if let Some(starting_length) = rules.starting_length {
+            // The head is always there
+            let body_length = starting_length.max(1) - 1;
+            for (snake, stats) in self.snakes.iter_mut().zip(&mut self.stats) {
+                if body_length < snake.body.len() {
+                    snake.body.truncate(body_length);
+                    stats.max_length = body_length + 1;
+                } else {
+                    snake.increment_size = body_length - snake.body.len();
+                }
+            }
         }
+        self.rules = rules;
 
-        self.move_body();
-        self.move_head(&direction);
+        self
+    }
+
+    /// Moves the first snake. The other ones, if any, keep their direction
+    pub fn play(&mut self, direction: Direction) -> Result<SnakeGameSnapshot, SnakeError> {
+        let id = SnakeId(0);
+        let (snapshot, mut outcomes) = self.play_many(&HashMap::from([(id, direction)]));
 
-        if self.on_walls(&self.snake_head) {
-            return Err(SnakeError::OnWall(self.snake_head.clone()));
+        match outcomes.remove(&id) {
+            Some(SnakeOutcome::Died(error)) => Err(error),
+            _ => Ok(snapshot),
         }
+    }
+
+    /// Moves all alive snakes at once. The snakes without a direction keep their own.
+    /// Returns the outcome of every snake: the dead ones leave the map
+    pub fn play_many(
+        &mut self,
+        directions: &HashMap<SnakeId, Direction>,
+    ) -> (SnakeGameSnapshot, HashMap<SnakeId, SnakeOutcome>) {
+        info!("play with {:?}", directions);
+
+        self.age_debris();
+        self.ticks += 1;
 
-        if self.on_snake_body(&self.snake_head) {
-            return Err(SnakeError::OnSnake(self.snake_head.clone()));
+        let mut outcomes: HashMap<SnakeId, SnakeOutcome> = HashMap::new();
+        let mut old_heads = HashMap::new();
+        for index in 0..self.snakes.len() {
+            let id = SnakeId(index);
+            if !self.snakes[index].alive {
+                outcomes.insert(id, SnakeOutcome::Dead);
+                continue;
+            }
+
+            let direction = directions
+                .get(&id)
+                .copied()
+                .unwrap_or(self.snakes[index].direction);
+
+            let next = self
+                .resolve_reversal(index, direction)
+                .and_then(|direction| self.next_head(index, direction));
+            match next {
+                Ok((next_head, direction)) => {
+                    let snake = &mut self.snakes[index];
+                    if direction != snake.direction {
+                        self.stats[index].turns += 1;
+                    }
+                    snake.move_body();
+                    old_heads.insert(id, std::mem::replace(&mut snake.head, next_head));
+                    snake.direction = direction;
+                }
+                Err(error) => {
+                    outcomes.insert(id, SnakeOutcome::Died(error));
+                }
+            }
         }
 
-        let food_ate = self.on_food(&self.snake_head);
-        if food_ate {
-            self.increment_size = 1;
-            self.food = self.generate_random_food_position()
+        // Collisions are checked once every snake is moved, looking only at the positions,
+        // so the snake order doesn't matter
+        let mut cuts = vec![];
+        for (index, snake) in self.snakes.iter().enumerate() {
+            let id = SnakeId(index);
+            let old_head = match old_heads.get(&id) {
+                // Not moved
+                None => continue,
+                Some(p) => p,
+            };
+            let head = &snake.head;
+
+            let head_on: Vec<SnakeId> = old_heads
+                .iter()
+                .filter(|(other_id, other_old_head)| {
+                    let other_head = &self.snakes[other_id.0].head;
+                    **other_id != id
+                        && (other_head == head
+                            || (other_head == old_head && *other_old_head == head))
+                })
+                .map(|(other_id, _)| *other_id)
+                .collect();
+            if !head_on.is_empty() {
+                let mut snakes = head_on;
+                snakes.push(id);
+                snakes.sort();
+                outcomes.insert(
+                    id,
+                    SnakeOutcome::Died(SnakeError::HeadOn(snakes, head.clone())),
+                );
+                continue;
+            }
+
+            // The snakes died in this tick are still there
+            let on_other_snake = self
+                .snakes
+                .iter()
+                .enumerate()
+                .find(|(i, other)| *i != index && other.alive && other.contains(head));
+            if let Some((other_index, _)) = on_other_snake {
+                let error = SnakeError::OnOtherSnake(SnakeId(other_index), head.clone());
+                outcomes.insert(id, SnakeOutcome::Died(error));
+                continue;
+            }
+
+            if let Some(body_index) = snake.body_index(head) {
+                match self.rules.self_collision {
+                    SelfCollision::Kill => {
+                        let error = SnakeError::OnSnake(head.clone());
+                        outcomes.insert(id, SnakeOutcome::Died(error));
+                        continue;
+                    }
+                    SelfCollision::CutTail => cuts.push((index, body_index)),
+                    SelfCollision::Ignore => {}
+                }
+            }
+
+            outcomes.insert(id, SnakeOutcome::Alive);
         }
 
-        self.direction = direction;
+        for (id, outcome) in &outcomes {
+            if let SnakeOutcome::Died(error) = outcome {
+                self.snakes[id.0].alive = false;
+                self.stats[id.0].death = Some(error.clone());
+            }
+        }
 
-        Ok(self.snapshot_with_food_ate(food_ate))
+        let mut severed_tails = vec![vec![]; self.snakes.len()];
+        for (index, body_index) in cuts {
+            if !self.snakes[index].alive {
+                continue;
+            }
+            let severed_tail = self.snakes[index].body.split_off(body_index);
+            self.leave_severed_tail(&severed_tail);
+            severed_tails[index] = severed_tail;
+        }
+
+        let mut food_ate = vec![None; self.snakes.len()];
+        for (index, snake) in self.snakes.iter_mut().enumerate() {
+            if !snake.alive {
+                continue;
+            }
+
+            if snake.head == self.food {
+                snake.increment_size += self.rules.growth_per_food;
+                food_ate[index] = Some(FoodKind::Food);
+            }
+
+            let temporary_food_index = self
+                .temporary_food
+                .iter()
+                .position(|d| d.position == snake.head);
+            if let Some(temporary_food_index) = temporary_food_index {
+                self.temporary_food.remove(temporary_food_index);
+                snake.increment_size += self.rules.growth_per_food;
+                food_ate[index] = Some(FoodKind::Debris);
+            }
+        }
+        if self
+            .snakes
+            .iter()
+            .any(|snake| snake.alive && snake.head == self.food)
+        {
+            // Left where it was eaten when no cell is free
+            match self.generate_random_food_position() {
+                Some(food) => self.food = food,
+                None => self.board_full = true,
+            }
+        }
+
+        self.count_tick(&food_ate);
+
+        (self.snapshot_with(food_ate, severed_tails), outcomes)
+    }
+
+    pub fn rules(&self) -> &GameRules {
+        &self.rules
+    }
+
+    /// Indexed by `SnakeId`
+    pub fn stats(&self) -> &[SnakeStats] {
+        &self.stats
+    }
+
+    pub fn snake_count(&self) -> usize {
+        self.snakes.len()
+    }
+
+    /// The current direction of each snake
+    pub fn directions(&self) -> HashMap<SnakeId, Direction> {
+        self.snakes
+            .iter()
+            .enumerate()
+            .map(|(index, snake)| (SnakeId(index), snake.direction))
+            .collect()
+    }
+
+    /// The current direction of the snake, if in the level
+    pub fn direction(&self, id: SnakeId) -> Option<Direction> {
+        self.snakes.get(id.0).map(|snake| snake.direction)
     }
 
     pub fn snapshot(&self) -> SnakeGameSnapshot {
-        self.snapshot_with_food_ate(false)
+        self.snapshot_with(
+            vec![None; self.snakes.len()],
+            vec![vec![]; self.snakes.len()],
+        )
     }
 
     pub fn dimension(&self) -> (usize, usize) {
         self.map.dimension
     }
 
+    /// The cells out of the walls: a snake so long fills the board
+    pub fn open_cells(&self) -> usize {
+        self.map
+            .iter()
+            .flat_map(|row| row.iter())
+            .filter(|cell| matches!(cell, CellField::Empty))
+            .count()
+    }
+
     pub fn on_walls(&self, position: &Position) -> bool {
         position.x >= self.map.dimension.0
             || position.y >= self.map.dimension.1
             || matches!(self.map[position.y][position.x], CellField::Wall)
     }
 
-    fn snapshot_with_food_ate(&self, food_ate: bool) -> SnakeGameSnapshot {
-        let mut snake_snapshot = self.snake_body.clone();
-        snake_snapshot.insert(0, self.snake_head.clone());
+    fn snapshot_with(
+        &self,
+        food_ate: Vec<Option<FoodKind>>,
+        severed_tails: Vec<Vec<Position>>,
+    ) -> SnakeGameSnapshot {
+        let snakes = self
+            .snakes
+            .iter()
+            .zip(food_ate)
+            .zip(severed_tails)
+            .enumerate()
+            .map(|(index, ((snake, food_ate), severed_tail))| SnakeSnapshot {
+                id: SnakeId(index),
+                alive: snake.alive,
+                direction: snake.direction,
+                body: if snake.alive { snake.pieces() } else { vec![] },
+                food_ate,
+                severed_tail,
+            })
+            .collect();
 
         SnakeGameSnapshot {
             food: self.food.clone(),
-            snake: snake_snapshot,
-            food_ate,
+            snakes,
+            temporary_walls: positions(&self.temporary_walls),
+            temporary_food: positions(&self.temporary_food),
+            tick: self.ticks,
+            board_full: self.board_full,
         }
     }
 
-    fn on_food(&self, position: &Position) -> bool {
-        position == &self.food
+    /// Walls or temporary walls
+    fn blocked(&self, position: &Position) -> bool {
+        self.on_walls(position) || self.temporary_walls.iter().any(|d| &d.position == position)
     }
 
-    fn on_snake_body(&self, position: &Position) -> bool {
-        self.snake_body.contains(position)
+    /// Turns the severed segments into debris, as the rules say.
+    /// The first segment is under the head, so it is always dropped
+    fn leave_severed_tail(&mut self, severed_tail: &[Position]) {
+        let (debris, ticks) = match self.rules.severed_tail {
+            SeveredTail::Vanish => return,
+            SeveredTail::Walls { ticks } => (&mut self.temporary_walls, ticks),
+            SeveredTail::Food { ticks } => (&mut self.temporary_food, ticks),
+        };
+        if ticks == 0 {
+            return;
+        }
+
+        debris.extend(severed_tail.iter().skip(1).map(|position| Debris {
+            position: position.clone(),
+            ticks_left: ticks,
+        }));
     }
 
-    fn move_head(&mut self, direction: &Direction) {
-        let (dx, dy): (isize, isize) = match direction {
-            Direction::Up => (0, 1),
-            Direction::Down => (0, -1),
-            Direction::Left => (-1, 0),
-            Direction::Right => (1, 0),
-        };
-        self.snake_head.x = (self.snake_head.x as isize + dx) as usize;
-        self.snake_head.y = (self.snake_head.y as isize + dy) as usize;
+    /// Updates the stats of the snakes alive after the tick
+    fn count_tick(&mut self, food_ate: &[Option<FoodKind>]) {
+        let alive = self.snakes.iter().zip(&mut self.stats).zip(food_ate);
+        for ((snake, stats), food_ate) in alive.filter(|((snake, _), _)| snake.alive) {
+            stats.ticks += 1;
+            if food_ate.is_some() {
+                stats.foods += 1;
+            }
+            stats.max_length = stats.max_length.max(snake.body.len() + 1);
+            stats.visit(&snake.head);
+        }
     }
 
-    fn move_body(&mut self) {
-        if self.increment_size > 0 {
-            let new_piece = self.snake_head.clone();
-            self.snake_body.insert(0, new_piece);
-            self.increment_size -= 1;
-        } else {
-            let mut tail = match self.snake_body.pop() {
-                // Means the snake is just its head, so nothing to do
-                None => return,
-                Some(p) => p,
-            };
+    fn age_debris(&mut self) {
+        for debris in [&mut self.temporary_walls, &mut self.temporary_food] {
+            debris.retain_mut(|d| {
+                d.ticks_left -= 1;
+                d.ticks_left > 0
+            });
+        }
+    }
+
+    fn on_snake(&self, position: &Position) -> bool {
+        self.snakes
+            .iter()
+            .any(|snake| snake.alive && snake.contains(position))
+    }
+
+    /// Applies the reversal rule, returning the direction the snake will follow
+    fn resolve_reversal(
+        &mut self,
+        index: usize,
+        direction: Direction,
+    ) -> Result<Direction, SnakeError> {
+        let dimension = self.map.dimension;
+        let snake = &mut self.snakes[index];
+        if snake.direction.allows(&direction) {
+            return Ok(direction);
+        }
+
+        match self.rules.reversal {
+            Reversal::Ignore => Ok(snake.direction),
+            Reversal::FlipSnake => Ok(snake.flip(dimension)),
+            Reversal::Kill => Err(SnakeError::Reversed(snake.head.clone())),
+        }
+    }
 
-            tail.x = self.snake_head.x;
-            tail.y = self.snake_head.y;
-            self.snake_body.insert(0, tail);
+    /// Calculates where the head goes next, applying the wall rule
+    fn next_head(
+        &mut self,
+        index: usize,
+        direction: Direction,
+    ) -> Result<(Position, Direction), SnakeError> {
+        let head = &self.snakes[index].head;
+        let next = step(head, &direction);
+        if !self.blocked(&next) {
+            return Ok((next, direction));
+        }
+
+        match self.rules.wall_collision {
+            WallCollision::Kill => Err(SnakeError::OnWall(next)),
+            WallCollision::Wrap => match self.wrap(head, &direction) {
+                Some(next) => Ok((next, direction)),
+                None => Err(SnakeError::OnWall(next)),
+            },
+            WallCollision::Bounce => {
+                let dimension = self.map.dimension;
+                let direction = self.snakes[index].flip(dimension);
+                let next = step(&self.snakes[index].head, &direction);
+                if self.blocked(&next) {
+                    return Err(SnakeError::OnWall(next));
+                }
+                Ok((next, direction))
+            }
         }
     }
 
-    fn generate_random_food_position(&self) -> Position {
+    /// Walks from `from` following `direction`, crossing the map borders,
+    /// till a free cell is found
+    fn wrap(&self, from: &Position, direction: &Direction) -> Option<Position> {
+        let (width, height) = self.map.dimension;
+        let (dx, dy) = direction.delta();
+
+        let mut position = from.clone();
+        for _ in 0..width.max(height) {
+            position.x = (position.x as isize + dx).rem_euclid(width as isize) as usize;
+            position.y = (position.y as isize + dy).rem_euclid(height as isize) as usize;
+
+            if !self.blocked(&position) {
+                return Some(position);
+            }
+        }
+
+        None
+    }
+
+    /// None when the snakes, the walls and the debris leave no cell free
+    fn generate_random_food_position(&mut self) -> Option<Position> {
+        let free = (0..self.map.dimension.1).any(|y| {
+            (0..self.map.dimension.0).any(|x| {
+                let position = Position::new(x, y);
+                !self.on_snake(&position)
+                    && !self.blocked(&position)
+                    && !self.temporary_food.iter().any(|d| d.position == position)
+            })
+        });
+        if !free {
+            return None;
+        }
+
         loop {
-            let y = fastrand::usize(0..self.map.dimension.0);
-            let x = fastrand::usize(0..self.map.dimension.1);
+            let x = self.food_rng.usize(0..self.map.dimension.0);
+            let y = self.food_rng.usize(0..self.map.dimension.1);
             let position = Position::new(x, y);
 
             debug!("position generated {:?}", position);
 
-            if self.on_snake_body(&position) {
+            if self.on_snake(&position) {
                 continue;
             }
 
-            if self.on_walls(&position) {
+            if self.blocked(&position) {
                 continue;
             }
 
-            if self.snake_head == position {
+            if self.temporary_food.iter().any(|d| d.position == position) {
                 continue;
             }
 
-            break position;
+            break Some(position);
         }
     }
 }
 
+/// The position next to `position` following `direction`.
+/// The returned position can be outside the map.
+fn step(position: &Position, direction: &Direction) -> Position {
+    let (dx, dy) = direction.delta();
+    Position::new(
+        (position.x as isize + dx) as usize,
+        (position.y as isize + dy) as usize,
+    )
+}
+
+/// The direction to follow to go from `from` to the adjacent `to`
+fn direction_between(from: &Position, to: &Position, dimension: (usize, usize)) -> Direction {
+    let (width, height) = dimension;
+
+    if from.y == to.y {
+        if (from.x + 1) % width == to.x {
+            Direction::Right
+        } else {
+            Direction::Left
+        }
+    } else if (from.y + 1) % height == to.y {
+        Direction::Up
+    } else {
+        Direction::Down
+    }
+}
+
 impl FromStr for SnakeGame {
     type Err = ();
 
@@ -189,48 +693,165 @@ impl FromStr for SnakeGame {
                     .collect(),
             );
         }
+        let dimension = (map[0].len(), map.len());
 
         let food = lines.next().unwrap();
         let food = food.split_once(',').unwrap();
         let food = Position::new(food.0.parse().unwrap(), food.1.parse().unwrap());
 
-        let snake = lines.next().unwrap();
-        let mut snake: Vec<Position> = snake
-            .split(';')
-            .map(|t| {
-                let t = t.split_once(',').unwrap();
-                Position::new(t.0.parse().unwrap(), t.1.parse().unwrap())
+        // Every remaining line is a snake
+        let snakes: Vec<Snake> = lines
+            .map(|snake| {
+                let pieces = snake
+                    .split(';')
+                    .map(|t| {
+                        let t = t.split_once(',').unwrap();
+                        Position::new(t.0.parse().unwrap(), t.1.parse().unwrap())
+                    })
+                    .collect();
+                Snake::new(pieces, dimension)
             })
             .collect();
-
-        let snake_head = snake.remove(0);
-        let snake_body = snake;
+        if snakes.is_empty() {
+            return Err(());
+        }
+        let stats = snakes
+            .iter()
+            .map(|snake| SnakeStats::new(dimension, &snake.head, snake.body.len() + 1))
+            .collect();
 
         Ok(Self {
             map: Map {
-                dimension: (map[0].len(), map.len()),
+                dimension,
                 rows: map.into_iter().map(|cells| Row { cells }).collect(),
             },
-            snake_head,
-            snake_body,
+            snakes,
             food,
-            direction: Direction::Up,
-            increment_size: 0,
+            rules: GameRules::default(),
+            temporary_walls: vec![],
+            temporary_food: vec![],
+            stats,
+            ticks: 0,
+            food_rng: FoodRng(fastrand::u64(..)),
+            board_full: false,
         })
     }
 }
 
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SnakeSnapshot {
+    pub id: SnakeId,
+    pub alive: bool,
+    /// Where the head is heading
+    pub direction: Direction,
+    /// The snake pieces, the head first. Empty if the snake is dead
+    pub body: Vec<Position>,
+    /// What the snake ate in this tick, if any
+    pub food_ate: Option<FoodKind>,
+    /// The segments lost biting the snake body in this tick
+    pub severed_tail: Vec<Position>,
+}
+
+impl SnakeSnapshot {
+    pub fn shapes(&self, dimension: (usize, usize)) -> Vec<PieceShape> {
+        piece_shapes(&self.body, Some(self.direction), dimension)
+    }
+}
+
+/// How each piece of the body looks, from the pieces around it. Through the walls too.
+/// Without a heading, the head goes on from the piece behind it
+pub fn piece_shapes(
+    body: &[Position],
+    heading: Option<Direction>,
+    dimension: (usize, usize),
+) -> Vec<PieceShape> {
+    (0..body.len())
+        .map(|index| {
+            if index == 0 {
+                let heading = heading.or_else(|| {
+                    body.get(1)
+                        .map(|neck| direction_between(neck, &body[0], dimension))
+                });
+                return PieceShape::Head(heading.unwrap_or(Direction::Up));
+            }
+
+            let ahead = direction_between(&body[index], &body[index - 1], dimension);
+            match body.get(index + 1) {
+                None => PieceShape::Tail(ahead),
+                Some(behind) => {
+                    let from = direction_between(behind, &body[index], dimension);
+                    if from == ahead {
+                        PieceShape::Straight(ahead)
+                    } else {
+                        PieceShape::Corner { from, to: ahead }
+                    }
+                }
+            }
+        })
+        .collect()
+}
+
+/// A piece of a snake, with the directions toward the head
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PieceShape {
+    Head(Direction),
+    Straight(Direction),
+    /// Entered going `from`, left going `to`
+    Corner {
+        from: Direction,
+        to: Direction,
+    },
+    /// The last piece, the one ahead in the direction
+    Tail(Direction),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct SnakeGameSnapshot {
-    pub snake: Vec<Position>,
+    /// Indexed by `SnakeId`
+    pub snakes: Vec<SnakeSnapshot>,
     pub food: Position,
-    pub food_ate: bool,
+    pub temporary_walls: Vec<Position>,
+    pub temporary_food: Vec<Position>,
+    /// The ticks played
+    pub tick: usize,
+    /// No cell is left for the food: the game is won
+    pub board_full: bool,
+}
+
+impl SnakeGameSnapshot {
+    pub fn food_ate(&self) -> bool {
+        self.snakes.iter().any(|snake| snake.food_ate.is_some())
+    }
+
+    /// Alone, a snake plays till the end or till it fills the board.
+    /// Otherwise the game is over when one snake at most is alive
+    pub fn is_over(&self) -> bool {
+        let alive = self.snakes.iter().filter(|snake| snake.alive).count();
+        self.board_full || alive == 0 || (self.snakes.len() > 1 && alive == 1)
+    }
+
+    /// The last snake alive, if more than one played
+    pub fn winner(&self) -> Option<SnakeId> {
+        let mut alive = self.snakes.iter().filter(|snake| snake.alive);
+        match (alive.next(), alive.next()) {
+            (Some(snake), None) if self.snakes.len() > 1 => Some(snake.id),
+            _ => None,
+        }
+    }
+}
+
+fn positions(debris: &[Debris]) -> Vec<Position> {
+    debris.iter().map(|d| d.position.clone()).collect()
 }
 
 #[cfg(test)]
 mod tests {
+    use std::collections::HashMap;
+
     use crate::snake::{
-        common::{Direction, Position},
-        game::SnakeError,
+        common::{Direction, FoodKind, Position, SnakeId, Turn},
+        game::{piece_shapes, PieceShape, SnakeError, SnakeOutcome},
+        rules::{GameRules, Reversal, SelfCollision, SeveredTail, WallCollision},
     };
 
     use super::SnakeGame;
@@ -260,17 +881,36 @@ mod tests {
         let mut game = create_game();
 
         let snapshot = game.play(Direction::Up).unwrap();
-        assert_eq!(snapshot.snake[0], Position::new(2, 3));
-        assert_eq!(snapshot.snake[1], Position::new(2, 2));
-        assert_eq!(snapshot.snake.len(), 2);
+        assert_eq!(snapshot.snakes[0].body[0], Position::new(2, 3));
+        assert_eq!(snapshot.snakes[0].body[1], Position::new(2, 2));
+        assert_eq!(snapshot.snakes[0].body.len(), 2);
         let snapshot = game.play(Direction::Left).unwrap();
-        assert_eq!(snapshot.snake[0], Position::new(1, 3));
-        assert_eq!(snapshot.snake[1], Position::new(2, 3));
-        assert_eq!(snapshot.snake.len(), 2);
+        assert_eq!(snapshot.snakes[0].body[0], Position::new(1, 3));
+        assert_eq!(snapshot.snakes[0].body[1], Position::new(2, 3));
+        assert_eq!(snapshot.snakes[0].body.len(), 2);
         let snapshot = game.play(Direction::Down).unwrap();
-        assert_eq!(snapshot.snake[0], Position::new(1, 2));
-        assert_eq!(snapshot.snake[1], Position::new(1, 3));
-        assert_eq!(snapshot.snake.len(), 2);
+        assert_eq!(snapshot.snakes[0].body[0], Position::new(1, 2));
+        assert_eq!(snapshot.snakes[0].body[1], Position::new(1, 3));
+        assert_eq!(snapshot.snakes[0].body.len(), 2);
+    }
+
+    #[test]
+    fn test_snake_should_turn_relative_to_its_direction() {
+        let mut game = create_game();
+        assert_eq!(game.direction(SnakeId(0)), Some(Direction::Up));
+
+        let direction = game.direction(SnakeId(0)).unwrap().turn(Turn::Right);
+        let snapshot = game.play(direction).unwrap();
+        assert_eq!(snapshot.snakes[0].body[0], Position::new(3, 2));
+
+        let direction = game.direction(SnakeId(0)).unwrap().turn(Turn::Right);
+        let snapshot = game.play(direction).unwrap();
+        assert_eq!(snapshot.snakes[0].body[0], Position::new(3, 1));
+
+        let direction = game.direction(SnakeId(0)).unwrap().turn(Turn::Left);
+        let snapshot = game.play(direction).unwrap();
+        assert_eq!(snapshot.snakes[0].body[0], Position::new(4, 1));
+        assert_eq!(game.direction(SnakeId(1)), None);
     }
 
     #[test]
@@ -279,25 +919,401 @@ mod tests {
 
         _ = game.play(Direction::Up).unwrap();
         let snapshot = game.play(Direction::Up).unwrap();
-        assert_eq!(snapshot.snake[0], Position::new(2, 4));
-        assert_eq!(snapshot.snake.len(), 2);
+        assert_eq!(snapshot.snakes[0].body[0], Position::new(2, 4));
+        assert_eq!(snapshot.snakes[0].body.len(), 2);
 
         _ = game.play(Direction::Right).unwrap();
         let snapshot = game.play(Direction::Right).unwrap();
-        assert_eq!(snapshot.snake[0], Position::new(4, 4));
-        assert_eq!(snapshot.snake.len(), 2);
+        assert_eq!(snapshot.snakes[0].body[0], Position::new(4, 4));
+        assert_eq!(snapshot.snakes[0].body.len(), 2);
+        assert_eq!(snapshot.snakes[0].food_ate, Some(FoodKind::Food));
+        assert_eq!(snapshot.tick, 4);
 
         let snapshot = game.play(Direction::Right).unwrap();
-        assert_eq!(snapshot.snake[0], Position::new(5, 4));
-        assert_eq!(snapshot.snake[1], Position::new(4, 4));
-        assert_eq!(snapshot.snake[2], Position::new(3, 4));
-        assert_eq!(snapshot.snake.len(), 3);
+        assert_eq!(snapshot.snakes[0].body[0], Position::new(5, 4));
+        assert_eq!(snapshot.snakes[0].body[1], Position::new(4, 4));
+        assert_eq!(snapshot.snakes[0].body[2], Position::new(3, 4));
+        assert_eq!(snapshot.snakes[0].body.len(), 3);
+
+        let snapshot = game.play(Direction::Right).unwrap();
+        assert_eq!(snapshot.snakes[0].body[0], Position::new(6, 4));
+        assert_eq!(snapshot.snakes[0].body[1], Position::new(5, 4));
+        assert_eq!(snapshot.snakes[0].body[2], Position::new(4, 4));
+        assert_eq!(snapshot.snakes[0].body.len(), 3);
+    }
+
+    #[test]
+    fn test_snake_stats_should_be_counted() {
+        let mut game = create_game().with_rules(GameRules {
+            reversal: Reversal::Kill,
+            ..GameRules::default()
+        });
+
+        for direction in [
+            Direction::Up,
+            Direction::Up,
+            Direction::Right,
+            Direction::Right,
+        ] {
+            game.play(direction).unwrap();
+        }
+        // Dying before the new food can be met
+        let error = game.play(Direction::Left).unwrap_err();
+
+        let stats = &game.stats()[0];
+        assert_eq!(stats.ticks, 4);
+        assert_eq!(stats.foods, 1);
+        assert_eq!(stats.ticks_per_food(), Some(4.));
+        assert_eq!(stats.max_length, 2);
+        assert_eq!(stats.turns, 1);
+        assert_eq!(stats.death, Some(error));
+        assert_eq!(stats.visits[2][2], 1);
+        assert_eq!(stats.visits[4][4], 1);
+        assert_eq!(stats.visits.iter().flatten().sum::<usize>(), 5);
+    }
+
+    #[test]
+    fn test_seeded_games_should_play_the_same() {
+        let moves = [
+            Direction::Up,
+            Direction::Up,
+            Direction::Right,
+            Direction::Right,
+            Direction::Up,
+            Direction::Left,
+        ];
+        let play = |game: &mut SnakeGame| -> Vec<_> {
+            moves
+                .iter()
+                .map(|direction| game.play(*direction).unwrap())
+                .collect()
+        };
+
+        let mut game = create_game().with_seed(42);
+        assert_eq!(game.seed(), 42);
+        let mut clone = game.clone();
+        let snapshots = play(&mut game);
+        assert_eq!(play(&mut create_game().with_seed(42)), snapshots);
+        assert_eq!(play(&mut clone), snapshots);
+        assert_eq!(game.snapshot(), clone.snapshot());
+    }
+
+    #[test]
+    fn test_game_should_be_shared_between_threads() {
+        // As the resources of Bevy
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<SnakeGame>();
+    }
 
+    #[test]
+    fn test_snake_pieces_should_be_shaped_by_their_neighbours() {
+        let mut game = create_game().with_rules(GameRules {
+            starting_length: Some(4),
+            ..GameRules::default()
+        });
+        game.play(Direction::Up).unwrap();
         let snapshot = game.play(Direction::Right).unwrap();
-        assert_eq!(snapshot.snake[0], Position::new(6, 4));
-        assert_eq!(snapshot.snake[1], Position::new(5, 4));
-        assert_eq!(snapshot.snake[2], Position::new(4, 4));
-        assert_eq!(snapshot.snake.len(), 3);
+
+        // Head on 3,3, then 2,3, 2,2 and the tail on 2,1
+        assert_eq!(
+            snapshot.snakes[0].shapes(game.dimension()),
+            vec![
+                PieceShape::Head(Direction::Right),
+                PieceShape::Corner {
+                    from: Direction::Up,
+                    to: Direction::Right
+                },
+                PieceShape::Straight(Direction::Up),
+                PieceShape::Tail(Direction::Up),
+            ]
+        );
+
+        // The ghosts have no heading
+        let body = &snapshot.snakes[0].body;
+        assert_eq!(
+            piece_shapes(body, None, game.dimension()),
+            snapshot.snakes[0].shapes(game.dimension())
+        );
+        assert_eq!(
+            piece_shapes(&body[..1], None, game.dimension()),
+            vec![PieceShape::Head(Direction::Up)]
+        );
+    }
+
+    #[test]
+    fn test_snake_should_wrap_through_walls() {
+        let mut game = create_game().with_rules(GameRules {
+            wall_collision: WallCollision::Wrap,
+            ..GameRules::default()
+        });
+
+        for _ in 0..4 {
+            game.play(Direction::Up).unwrap();
+        }
+        let snapshot = game.play(Direction::Up).unwrap();
+        assert_eq!(snapshot.snakes[0].body[0], Position::new(2, 1));
+        assert_eq!(snapshot.snakes[0].body[1], Position::new(2, 6));
+    }
+
+    #[test]
+    fn test_snake_should_bounce_on_walls() {
+        let mut game = create_game().with_rules(GameRules {
+            wall_collision: WallCollision::Bounce,
+            ..GameRules::default()
+        });
+
+        for _ in 0..4 {
+            game.play(Direction::Up).unwrap();
+        }
+        let snapshot = game.play(Direction::Up).unwrap();
+        assert_eq!(
+            snapshot.snakes[0].body,
+            vec![Position::new(2, 4), Position::new(2, 5)]
+        );
+
+        // The snake is now going down
+        let snapshot = game.play(Direction::Down).unwrap();
+        assert_eq!(
+            snapshot.snakes[0].body,
+            vec![Position::new(2, 3), Position::new(2, 4)]
+        );
+    }
+
+    #[test]
+    fn test_snake_reversal_rules() {
+        let mut game = create_game().with_rules(GameRules {
+            reversal: Reversal::FlipSnake,
+            ..GameRules::default()
+        });
+        _ = game.play(Direction::Right).unwrap();
+        let snapshot = game.play(Direction::Left).unwrap();
+        assert_eq!(
+            snapshot.snakes[0].body,
+            vec![Position::new(1, 2), Position::new(2, 2)]
+        );
+
+        let mut game = create_game().with_rules(GameRules {
+            reversal: Reversal::Kill,
+            ..GameRules::default()
+        });
+        let result = game.play(Direction::Down);
+        assert_eq!(
+            result.err().unwrap(),
+            SnakeError::Reversed(Position::new(2, 2))
+        );
+    }
+
+    #[test]
+    fn test_snake_self_collision_rules() {
+        let rules = GameRules {
+            starting_length: Some(5),
+            ..GameRules::default()
+        };
+        let turns = [Direction::Up, Direction::Right, Direction::Down];
+
+        let mut game = create_game().with_rules(rules.clone());
+        for direction in turns {
+            game.play(direction).unwrap();
+        }
+        let result = game.play(Direction::Left);
+        assert_eq!(
+            result.err().unwrap(),
+            SnakeError::OnSnake(Position::new(2, 2))
+        );
+
+        let mut game = create_game().with_rules(GameRules {
+            self_collision: SelfCollision::CutTail,
+            ..rules
+        });
+        for direction in turns {
+            game.play(direction).unwrap();
+        }
+        let snapshot = game.play(Direction::Left).unwrap();
+        assert_eq!(
+            snapshot.snakes[0].body,
+            vec![
+                Position::new(2, 2),
+                Position::new(3, 2),
+                Position::new(3, 3),
+                Position::new(2, 3)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_snake_severed_tail_rules() {
+        let rules = GameRules {
+            self_collision: SelfCollision::CutTail,
+            severed_tail: SeveredTail::Walls { ticks: 2 },
+            starting_length: Some(6),
+            ..GameRules::default()
+        };
+        let turns = [
+            Direction::Up,
+            Direction::Right,
+            Direction::Down,
+            Direction::Left,
+        ];
+
+        let mut game = create_game().with_rules(rules.clone());
+        let mut snapshot = None;
+        for direction in turns {
+            snapshot = Some(game.play(direction).unwrap());
+        }
+        let snapshot = snapshot.unwrap();
+        assert_eq!(
+            snapshot.snakes[0].severed_tail,
+            vec![Position::new(2, 2), Position::new(2, 1)]
+        );
+        assert_eq!(snapshot.temporary_walls, vec![Position::new(2, 1)]);
+        let result = game.play(Direction::Down);
+        assert_eq!(
+            result.err().unwrap(),
+            SnakeError::OnWall(Position::new(2, 1))
+        );
+
+        let mut game = create_game().with_rules(GameRules {
+            severed_tail: SeveredTail::Food { ticks: 2 },
+            ..rules
+        });
+        for direction in turns {
+            game.play(direction).unwrap();
+        }
+        let snapshot = game.play(Direction::Down).unwrap();
+        assert_eq!(snapshot.snakes[0].food_ate, Some(FoodKind::Debris));
+        assert!(snapshot.temporary_food.is_empty());
+        let snapshot = game.play(Direction::Left).unwrap();
+        assert_eq!(snapshot.snakes[0].body.len(), 5);
+    }
+
+    #[test]
+    fn test_snakes_should_crash_head_on() {
+        let directions =
+            HashMap::from([(SnakeId(0), Direction::Up), (SnakeId(1), Direction::Down)]);
+
+        // On the same cell
+        let mut game = create_multi_snake_game(&["2,2;2,1", "2,4;2,5"]);
+        let (_, outcomes) = game.play_many(&directions);
+        let error = SnakeError::HeadOn(vec![SnakeId(0), SnakeId(1)], Position::new(2, 3));
+        assert_eq!(outcomes[&SnakeId(0)], SnakeOutcome::Died(error.clone()));
+        assert_eq!(outcomes[&SnakeId(1)], SnakeOutcome::Died(error));
+
+        // Swapping the cells
+        let mut game = create_multi_snake_game(&["2,2;2,1", "2,3;2,4"]);
+        let (_, outcomes) = game.play_many(&directions);
+        assert_eq!(
+            outcomes[&SnakeId(0)],
+            SnakeOutcome::Died(SnakeError::HeadOn(
+                vec![SnakeId(0), SnakeId(1)],
+                Position::new(2, 3)
+            ))
+        );
+        assert_eq!(
+            outcomes[&SnakeId(1)],
+            SnakeOutcome::Died(SnakeError::HeadOn(
+                vec![SnakeId(0), SnakeId(1)],
+                Position::new(2, 2)
+            ))
+        );
+    }
+
+    #[test]
+    fn test_snakes_should_start_where_their_heads_point() {
+        let game = create_multi_snake_game(&["2,2;2,1", "2,4;2,5", "4,3;3,3", "6,2"]);
+        assert_eq!(game.direction(SnakeId(0)), Some(Direction::Up));
+        assert_eq!(game.direction(SnakeId(1)), Some(Direction::Down));
+        assert_eq!(game.direction(SnakeId(2)), Some(Direction::Right));
+        assert_eq!(game.direction(SnakeId(3)), Some(Direction::Up));
+
+        // Through the edge of the board
+        let game = create_multi_snake_game(&["0,3;8,3"]);
+        assert_eq!(game.direction(SnakeId(0)), Some(Direction::Right));
+    }
+
+    #[test]
+    fn test_snakes_should_not_depend_on_order() {
+        let first = "2,2;2,1";
+        let second = "3,4;3,5";
+        let third = "6,2;6,1";
+
+        let mut game = create_multi_snake_game(&[first, second, third]);
+        let (snapshot, _) = game.play_many(&HashMap::from([(SnakeId(0), Direction::Right)]));
+        assert_eq!(snapshot.snakes[0].body[0], Position::new(3, 2));
+        assert_eq!(snapshot.snakes[1].body[0], Position::new(3, 3));
+        let (_, outcomes) = game.play_many(&HashMap::from([
+            (SnakeId(0), Direction::Up),
+            (SnakeId(1), Direction::Left),
+        ]));
+        assert_eq!(
+            outcomes[&SnakeId(0)],
+            SnakeOutcome::Died(SnakeError::OnOtherSnake(SnakeId(1), Position::new(3, 3)))
+        );
+        assert_eq!(outcomes[&SnakeId(1)], SnakeOutcome::Alive);
+        assert_eq!(outcomes[&SnakeId(2)], SnakeOutcome::Alive);
+
+        let mut game = create_multi_snake_game(&[third, second, first]);
+        let (snapshot, _) = game.play_many(&HashMap::from([(SnakeId(2), Direction::Right)]));
+        assert_eq!(snapshot.snakes[2].body[0], Position::new(3, 2));
+        let (snapshot, outcomes) = game.play_many(&HashMap::from([
+            (SnakeId(2), Direction::Up),
+            (SnakeId(1), Direction::Left),
+        ]));
+        assert_eq!(
+            outcomes[&SnakeId(2)],
+            SnakeOutcome::Died(SnakeError::OnOtherSnake(SnakeId(1), Position::new(3, 3)))
+        );
+        assert_eq!(outcomes[&SnakeId(1)], SnakeOutcome::Alive);
+        assert_eq!(outcomes[&SnakeId(0)], SnakeOutcome::Alive);
+
+        // The dead snake leaves the map
+        assert!(!snapshot.snakes[2].alive);
+        assert!(snapshot.snakes[2].body.is_empty());
+        let (_, outcomes) = game.play_many(&HashMap::new());
+        assert_eq!(outcomes[&SnakeId(2)], SnakeOutcome::Dead);
+    }
+
+    #[test]
+    fn test_snake_should_win_when_filling_the_board() {
+        let s = r#"
+4,4
+wwww
+w  w
+w  w
+wwww
+2,2
+2,1;1,1;1,2"#;
+        let mut game: SnakeGame = s.parse().unwrap();
+        assert_eq!(game.open_cells(), 4);
+
+        // The food can only go where the tail was
+        let snapshot = game.play(Direction::Up).unwrap();
+        assert_eq!(snapshot.food, Position::new(1, 2));
+        assert!(!snapshot.is_over());
+
+        let snapshot = game.play(Direction::Left).unwrap();
+        assert_eq!(snapshot.snakes[0].body.len(), game.open_cells());
+        assert!(snapshot.board_full);
+        assert!(snapshot.is_over());
+        assert_eq!(snapshot.winner(), None);
+    }
+
+    fn create_multi_snake_game(snakes: &[&str]) -> SnakeGame {
+        let s = format!(
+            r#"
+9,8
+wwwwwwwww
+w       w
+w       w
+w       w
+w       w
+w       w
+w       w
+wwwwwwwww
+6,6
+{}"#,
+            snakes.join("\n")
+        );
+        s.parse().unwrap()
     }
 
     fn create_game() -> SnakeGame {
@@ -7,6 +7,10 @@
 
 mod common;
 mod game;
+mod rules;
+mod stats;
 
 pub use common::*;
 pub use game::*;
+pub use rules::*;
+pub use stats::*;
@@ -0,0 +1,72 @@
+/// What happens when the snake head reaches a wall
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WallCollision {
+    /// The game is over
+    Kill,
+    /// The snake passes through the wall and appears on the first free cell
+    /// following its direction, wrapping around the map borders
+    Wrap,
+    /// The snake is flipped and goes back the way it came
+    Bounce,
+}
+
+/// What happens when the snake head reaches its own body
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SelfCollision {
+    /// The game is over
+    Kill,
+    /// The body is cut where the head bites it: the tail is lost
+    CutTail,
+    /// The snake moves through its own body
+    Ignore,
+}
+
+/// What the segments cut by `SelfCollision::CutTail` become
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SeveredTail {
+    /// They disappear
+    Vanish,
+    /// They block the snake for the given ticks
+    Walls { ticks: usize },
+    /// They can be eaten for the given ticks
+    Food { ticks: usize },
+}
+
+/// What happens when the player asks the snake to go back
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Reversal {
+    /// The request is ignored and the snake keeps its direction
+    Ignore,
+    /// The tail becomes the head and the snake moves from there
+    FlipSnake,
+    /// The game is over
+    Kill,
+}
+
+/// The rules `SnakeGame::play` follows.
+/// The default values are the classic ones.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GameRules {
+    pub wall_collision: WallCollision,
+    pub self_collision: SelfCollision,
+    pub severed_tail: SeveredTail,
+    pub reversal: Reversal,
+    /// How many pieces the snake grows for each food
+    pub growth_per_food: usize,
+    /// The snake length (head included) at the beginning of the game.
+    /// `None` keeps the snake described in the level
+    pub starting_length: Option<usize>,
+}
+
+impl Default for GameRules {
+    fn default() -> Self {
+        Self {
+            wall_collision: WallCollision::Kill,
+            self_collision: SelfCollision::Kill,
+            severed_tail: SeveredTail::Vanish,
+            reversal: Reversal::Ignore,
+            growth_per_food: 1,
+            starting_length: None,
+        }
+    }
+}
@@ -0,0 +1,48 @@
+use serde::{Deserialize, Serialize};
+
+use super::{common::Position, game::SnakeError};
+
+/// What a snake did in the game, counted by the engine
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SnakeStats {
+    /// The ticks the snake came out alive from
+    pub ticks: usize,
+    pub foods: usize,
+    pub max_length: usize,
+    /// The times the snake changed direction
+    pub turns: usize,
+    pub death: Option<SnakeError>,
+    /// How many ticks the head spent on each cell, by row
+    pub visits: Vec<Vec<usize>>,
+}
+
+impl SnakeStats {
+    pub(super) fn new(dimension: (usize, usize), head: &Position, length: usize) -> Self {
+        let (width, height) = dimension;
+        let mut stats = Self {
+            ticks: 0,
+            foods: 0,
+            max_length: length,
+            turns: 0,
+            death: None,
+            visits: vec![vec![0; width]; height],
+        };
+        stats.visit(head);
+        stats
+    }
+
+    /// None before the first food
+    pub fn ticks_per_food(&self) -> Option<f32> {
+        (self.foods > 0).then(|| self.ticks as f32 / self.foods as f32)
+    }
+
+    pub(super) fn visit(&mut self, position: &Position) {
+        if let Some(visits) = self
+            .visits
+            .get_mut(position.y)
+            .and_then(|row| row.get_mut(position.x))
+        {
+            *visits += 1;
+        }
+    }
+}
@@ -1,11 +1,33 @@
-use bevy::{input::keyboard::KeyboardInput, prelude::*};
+use std::{
+    collections::{HashMap, VecDeque},
+    f32::consts::{FRAC_PI_2, PI},
+    time::Duration,
+};
+
+use bevy::{input::keyboard::KeyboardInput, prelude::*, window::WindowResized};
 
 use crate::{
-    snake::{Direction, Position},
-    snake_plugin::events::FoodAteEvent,
+    configuration::{just_pressed_keys, Action, ControlScheme, Settings},
+    loading_plugin::GameState,
+    snake::{
+        piece_shapes, Direction, GameRules, PieceShape, Position, SnakeError, SnakeId, SnakeOutcome,
+    },
+    snake_plugin::events::{FoodAteEvent, TailCutEvent},
+    themes::{find_theme, parse_themes, Theme, THEMES},
 };
 
-use self::{components::*, events::GameTick, resources::GameTimerResource};
+use self::{
+    components::*,
+    events::{
+        GameActionEvent, GameTick, RestartEvent, RewindEvent, SnakeMovedEvent, SteerEvent,
+        ThemeChangedEvent,
+    },
+    resources::{
+        DirectionsResource, GameClockResource, GameTimerResource, GhostResource, HistoryResource,
+        InterpolationResource, LocalPlayersResource, SettingsResource, ThemeResource,
+        TurnsResource,
+    },
+};
 
 use self::{events::GameOverEvent, resources::DrawConfigurationResource};
 
@@ -18,76 +40,353 @@ const FOOD_Z: f32 = 0.;
 // Snake can overlap with foods,
 // so should be bigger
 const SNAKE_Z: f32 = 1.;
+// Under the live snakes
+const GHOST_Z: f32 = 0.5;
+
+// The pieces of the snake in `snake.png`, side by side, facing up
+const SNAKE_SPRITE_SIZE: f32 = 32.;
+const SNAKE_SPRITES: usize = 4;
+const HEAD_SPRITE: usize = 0;
+const STRAIGHT_SPRITE: usize = 1;
+/// Joins the bottom and the right side
+const CORNER_SPRITE: usize = 2;
+/// Joined on the top side
+const TAIL_SPRITE: usize = 3;
+
+/// How many ticks can be rewound
+pub const REWIND_TICKS: usize = 50;
+
+/// The tick at the beginning of the game
+const TICK_SECONDS: f32 = 0.5;
+/// The ticks get shorter every these foods, eaten by any snake
+const SPEED_UP_FOODS: usize = 5;
+const SPEED_UP_FACTOR: f32 = 0.9;
+const MIN_TICK_SECONDS: f32 = 0.15;
 
 pub struct SnakePlugin {
-    pub rect: UiRect<f32>,
-    pub level: &'static str,
+    /// The room left around the board, from the sides of the window: the HUD is on the top
+    pub margins: UiRect<f32>,
+    pub level: String,
+    pub rules: GameRules,
+    /// The snakes steered from this keyboard, in the order of the players in the key bindings
+    pub local_players: Vec<SnakeId>,
+    pub settings: Settings,
+    /// The game is played elsewhere: the snapshots come from `RemotePlugin` or `SpectatorPlugin`
+    pub remote: bool,
+    /// The seed of the foods, kept on restart for a game everybody can play the same.
+    /// New foods every game if missing
+    pub seed: Option<u64>,
 }
 
 impl Plugin for SnakePlugin {
     fn build(&self, app: &mut App) {
-        let snake_game: SnakeGame = self.level.parse().unwrap();
-
-        let dim = snake_game.dimension();
+        let mut snake_game = self
+            .level
+            .parse::<SnakeGame>()
+            .unwrap()
+            .with_rules(self.rules.clone());
+        if let Some(seed) = self.seed {
+            snake_game = snake_game.with_seed(seed);
+        }
 
-        let cell_width = (self.rect.right - self.rect.left) / dim.0 as f32;
-        let cell_height = (self.rect.top - self.rect.bottom) / dim.1 as f32;
-        let cell_size = cell_height.min(cell_width);
+        let window = app.world.resource::<WindowDescriptor>();
+        let draw_configuration = fit_board(
+            Vec2::new(window.width, window.height),
+            self.margins,
+            self.settings.min_cell_size,
+            snake_game.dimension(),
+        );
 
         let snapshot = snake_game.snapshot();
+        let directions = DirectionsResource(snake_game.directions());
+        let history = HistoryResource {
+            seed: self.seed,
+            initial: snake_game.clone(),
+            rewound: false,
+            ticks: VecDeque::new(),
+        };
+
+        let themes = parse_themes(THEMES).expect("invalid themes");
+        let theme = find_theme(&themes, &self.settings.theme).clone();
+        let font = app.world.resource::<AssetServer>().load(&theme.font);
+
+        app.insert_resource(InterpolationResource {
+            from: snapshot.clone(),
+            to: snapshot.clone(),
+        })
+        .insert_resource(snapshot)
+        .insert_resource(snake_game)
+        .insert_resource(directions)
+        .insert_resource(history)
+        .insert_resource(LocalPlayersResource(self.local_players.clone()))
+        .insert_resource(TurnsResource::default())
+        .insert_resource(SettingsResource {
+            settings: self.settings.clone(),
+            editors: 0,
+        })
+        .insert_resource(draw_configuration)
+        .insert_resource(GameTimerResource(Timer::from_seconds(TICK_SECONDS, true)))
+        .init_resource::<GameClockResource>()
+        .insert_resource(ClearColor(rgb(theme.background)))
+        .insert_resource(ThemeResource {
+            themes,
+            theme,
+            font,
+        })
+        .init_resource::<PbrBundles>()
+        .add_event::<GameOverEvent>()
+        .add_event::<FoodAteEvent>()
+        .add_event::<SnakeMovedEvent>()
+        .add_event::<TailCutEvent>()
+        .add_event::<GameTick>()
+        .add_event::<SteerEvent>()
+        .add_event::<GameActionEvent>()
+        .add_event::<RestartEvent>()
+        .add_event::<RewindEvent>()
+        .add_event::<ThemeChangedEvent>()
+        // Drawn and played once the assets are loaded
+        .add_system_set(
+            SystemSet::on_enter(GameState::Playing)
+                .with_system(draw_field)
+                .with_system(draw_snake),
+        )
+        .add_system_set(
+            SystemSet::on_update(GameState::Playing)
+                .with_system(keyboard_input.label("input"))
+                .with_system(change_direction.label("change_direction").after("input"))
+                .with_system(update_snake_head.label("update_snake").after("play"))
+                .with_system(update_snake_body.label("update_snake").after("play"))
+                .with_system(update_food.label("update_food").after("play"))
+                .with_system(update_temporary_walls.after("play"))
+                .with_system(update_temporary_food.after("play"))
+                .with_system(update_ghost.after("play"))
+                .with_system(shape_snakes.after("update_snake"))
+                .with_system(shape_ghost.after("play"))
+                .with_system(game_over.after("play")),
+        )
+        // Before anything is moved or spawned in the frame
+        .add_system(relayout_board.before("input"))
+        .add_system(tick_clock)
+        .add_system(switch_theme.label("switch_theme"))
+        .add_system(retheme_sprites.after("switch_theme"))
+        .add_system(retheme_texts.after("switch_theme"));
+
+        if !self.remote {
+            app.add_system_set(
+                SystemSet::on_update(GameState::Playing)
+                    .with_system(game_tick.label("game_tick"))
+                    .with_system(resolve_turns.after("game_tick").before("play"))
+                    .with_system(play.label("play"))
+                    .with_system(pause_game.after("play"))
+                    .with_system(restart_game.after("play"))
+                    .with_system(rewind_game.after("play"))
+                    .with_system(speed_up.after("play"))
+                    // The remote games have no timer to follow
+                    .with_system(remember_snapshot.label("remember_snapshot").after("play"))
+                    .with_system(
+                        interpolate_snakes
+                            .after("remember_snapshot")
+                            .after("update_snake"),
+                    ),
+            );
+        }
+    }
+}
 
-        app.insert_resource(snapshot)
-            .insert_resource(snake_game)
-            .insert_resource(Direction::Up)
-            .insert_resource(DrawConfigurationResource {
-                cell_size,
-                half_cell: cell_size / 2.,
-                origin: (self.rect.bottom, self.rect.left),
-            })
-            .insert_resource(GameTimerResource(Timer::from_seconds(0.5, true)))
-            .init_resource::<PbrBundles>()
-            .add_event::<GameOverEvent>()
-            .add_event::<FoodAteEvent>()
-            .add_event::<GameTick>()
-            .add_startup_system(draw_field)
-            .add_startup_system(draw_snake)
-            .add_system(game_tick)
-            .add_system(change_direction)
-            .add_system(play.label("play"))
-            .add_system(update_snake_head.after("play"))
-            .add_system(update_snake_body.after("play"))
-            .add_system(update_food.after("play"))
-            .add_system(game_over.after("play"));
+fn keyboard_input(
+    mut keyboard_input_events: EventReader<KeyboardInput>,
+    keyboard: Res<Input<KeyCode>>,
+    settings: Res<SettingsResource>,
+    local_players: Res<LocalPlayersResource>,
+    mut steer_event_writer: EventWriter<SteerEvent>,
+    mut game_action_event_writer: EventWriter<GameActionEvent>,
+) {
+    let keys = just_pressed_keys(&mut keyboard_input_events, &keyboard);
+    if settings.editing() {
+        return;
+    }
+
+    for key in keys {
+        match settings
+            .settings
+            .key_bindings
+            .action(key, local_players.0.len())
+        {
+            None => {}
+            Some(Action::Steer { player, direction }) => {
+                steer_event_writer.send(SteerEvent { player, direction })
+            }
+            Some(action) => game_action_event_writer.send(GameActionEvent(action)),
+        }
     }
 }
 
+/// Every input device ends up here
 fn change_direction(
-    mut keyboard_input_events: EventReader<KeyboardInput>,
-    mut current_direction: ResMut<Direction>,
+    mut steer_event_reader: EventReader<SteerEvent>,
+    local_players: Res<LocalPlayersResource>,
+    settings: Res<SettingsResource>,
+    mut directions: ResMut<DirectionsResource>,
+    mut turns: ResMut<TurnsResource>,
 ) {
-    let direction = keyboard_input_events
+    // The last one wins
+    for event in steer_event_reader.iter() {
+        let id = match local_players.0.get(event.player) {
+            None => continue,
+            Some(id) => *id,
+        };
+
+        match settings.settings.control_scheme {
+            ControlScheme::Absolute => {
+                directions.0.insert(id, event.direction);
+                turns.0.remove(&id);
+            }
+            ControlScheme::Relative => {
+                if let Some(turn) = settings.settings.control_scheme.turn(event.direction) {
+                    turns.0.insert(id, turn);
+                }
+            }
+        }
+    }
+}
+
+fn pause_game(
+    mut game_action_event_reader: EventReader<GameActionEvent>,
+    snake_game: Res<SnakeGame>,
+    mut game_timers: ResMut<GameTimerResource>,
+) {
+    for _ in game_action_event_reader
         .iter()
-        .filter_map(|ki| ki.key_code)
-        .filter_map(|kc| match kc {
-            KeyCode::Up => Some(Direction::Up),
-            KeyCode::Down => Some(Direction::Down),
-            KeyCode::Left => Some(Direction::Left),
-            KeyCode::Right => Some(Direction::Right),
-            _ => None,
-        })
-        .last();
+        .filter(|e| e.0 == Action::Pause)
+    {
+        // A game over stays paused
+        if snake_game.snapshot().is_over() {
+            continue;
+        }
 
-    *current_direction = match direction {
-        None => return,
-        Some(d) => d,
-    };
+        let game_timers = &mut game_timers.0;
+        if game_timers.paused() {
+            game_timers.unpause();
+        } else {
+            game_timers.pause();
+        }
+    }
+}
+
+fn restart_game(
+    mut game_action_event_reader: EventReader<GameActionEvent>,
+    mut history: ResMut<HistoryResource>,
+    mut snake_game: ResMut<SnakeGame>,
+    mut snapshot: ResMut<SnakeGameSnapshot>,
+    mut directions: ResMut<DirectionsResource>,
+    mut game_timers: ResMut<GameTimerResource>,
+    mut restart_event_writer: EventWriter<RestartEvent>,
+) {
+    let restarts = game_action_event_reader
+        .iter()
+        .filter(|e| e.0 == Action::Restart)
+        .count();
+    if restarts == 0 {
+        return;
+    }
+
+    history.ticks.clear();
+    history.rewound = false;
+    if history.seed.is_none() {
+        history.initial = history.initial.clone().with_seed(fastrand::u64(..));
+    }
+    restore(
+        history.initial.clone(),
+        &mut snake_game,
+        &mut snapshot,
+        &mut directions,
+    );
+
+    let game_timers = &mut game_timers.0;
+    game_timers.reset();
+    game_timers.unpause();
+
+    restart_event_writer.send(RestartEvent);
+}
+
+/// Goes back a tick and pauses, so the player can choose another way
+fn rewind_game(
+    mut game_action_event_reader: EventReader<GameActionEvent>,
+    mut history: ResMut<HistoryResource>,
+    mut snake_game: ResMut<SnakeGame>,
+    mut snapshot: ResMut<SnakeGameSnapshot>,
+    mut directions: ResMut<DirectionsResource>,
+    mut game_timers: ResMut<GameTimerResource>,
+    mut rewind_event_writer: EventWriter<RewindEvent>,
+) {
+    for _ in game_action_event_reader
+        .iter()
+        .filter(|e| e.0 == Action::Rewind)
+    {
+        let previous_game = match history.ticks.pop_back() {
+            // Back to the beginning
+            None => return,
+            Some(g) => g,
+        };
+
+        restore(
+            previous_game,
+            &mut snake_game,
+            &mut snapshot,
+            &mut directions,
+        );
+        history.rewound = true;
+        game_timers.0.pause();
+
+        rewind_event_writer.send(RewindEvent);
+    }
+}
+
+/// The game lasts while its timer runs, and goes back with it
+fn tick_clock(
+    time: Res<Time>,
+    game_timers: Res<GameTimerResource>,
+    mut tick_event_reader: EventReader<GameTick>,
+    mut restart_event_reader: EventReader<RestartEvent>,
+    mut rewind_event_reader: EventReader<RewindEvent>,
+    mut clock: ResMut<GameClockResource>,
+) {
+    if restart_event_reader.iter().count() > 0 {
+        *clock = GameClockResource::default();
+    }
+    for _ in rewind_event_reader.iter() {
+        clock.rewind();
+    }
+    for _ in tick_event_reader.iter() {
+        clock.tick();
+    }
+    if !game_timers.0.paused() {
+        clock.played += time.delta();
+    }
+}
+
+fn restore(
+    game: SnakeGame,
+    snake_game: &mut SnakeGame,
+    snapshot: &mut SnakeGameSnapshot,
+    directions: &mut DirectionsResource,
+) {
+    *snake_game = game;
+    *snapshot = snake_game.snapshot();
+    directions.0 = snake_game.directions();
 }
 
 fn game_tick(
     time: Res<Time>,
+    settings: Res<SettingsResource>,
     mut game_timers: ResMut<GameTimerResource>,
     mut tick_event_writer: EventWriter<GameTick>,
 ) {
+    // The game waits the keys to be rebound
+    if settings.editing() {
+        return;
+    }
+
     let game_timers = &mut game_timers.0;
     if !game_timers.tick(time.delta()).finished() {
         return;
@@ -98,43 +397,129 @@ fn game_tick(
     tick_event_writer.send(GameTick);
 }
 
+/// The turns follow where the snakes go when the tick comes
+fn resolve_turns(
+    mut tick_event: EventReader<GameTick>,
+    snake_game: Res<SnakeGame>,
+    mut turns: ResMut<TurnsResource>,
+    mut directions: ResMut<DirectionsResource>,
+) {
+    if tick_event.iter().count() == 0 {
+        return;
+    }
+
+    for (id, turn) in turns.0.drain() {
+        if let Some(direction) = snake_game.direction(id) {
+            directions.0.insert(id, direction.turn(turn));
+        }
+    }
+}
+
 fn play(
-    current_direction: Res<Direction>,
+    directions: Res<DirectionsResource>,
     mut tick_event: EventReader<GameTick>,
     mut snake_game: ResMut<SnakeGame>,
     mut snapshot: ResMut<SnakeGameSnapshot>,
+    mut history: ResMut<HistoryResource>,
     mut game_over_event_writer: EventWriter<GameOverEvent>,
+    mut snake_moved_event_writer: EventWriter<SnakeMovedEvent>,
 ) {
     if tick_event.iter().count() == 0 {
         return;
     }
 
-    let result = snake_game.play(*current_direction);
+    history.ticks.push_back(snake_game.clone());
+    if history.ticks.len() > REWIND_TICKS {
+        history.ticks.pop_front();
+    }
 
-    *snapshot = match result {
-        Ok(snapshot) => snapshot,
-        Err(error) => {
-            game_over_event_writer.send(GameOverEvent { error });
-            return;
+    let old_directions = snake_game.directions();
+    let (new_snapshot, outcomes) = snake_game.play_many(&directions.0);
+
+    for (id, outcome) in &outcomes {
+        if *outcome != SnakeOutcome::Alive {
+            continue;
         }
-    };
+        if let Some(direction) = snake_game.direction(*id) {
+            snake_moved_event_writer.send(SnakeMovedEvent {
+                player: id.0,
+                turned: old_directions.get(id) != Some(&direction),
+                length: new_snapshot.snakes[id.0].body.len(),
+            });
+        }
+    }
+
+    apply_tick(
+        &mut snapshot,
+        new_snapshot,
+        &outcomes,
+        history.rewound,
+        &mut game_over_event_writer,
+    );
+}
+
+/// Shows the new snapshot, till the game is over
+pub fn apply_tick(
+    snapshot: &mut SnakeGameSnapshot,
+    new_snapshot: SnakeGameSnapshot,
+    outcomes: &HashMap<SnakeId, SnakeOutcome>,
+    rewound: bool,
+    game_over_event_writer: &mut EventWriter<GameOverEvent>,
+) {
+    if !new_snapshot.is_over() {
+        *snapshot = new_snapshot;
+        return;
+    }
+
+    let mut errors: Vec<(usize, SnakeError)> = outcomes
+        .iter()
+        .filter_map(|(id, outcome)| match outcome {
+            SnakeOutcome::Died(error) => Some((id.0, error.clone())),
+            _ => None,
+        })
+        .collect();
+    errors.sort_by_key(|(player, _)| *player);
+
+    game_over_event_writer.send(GameOverEvent {
+        errors,
+        winner: new_snapshot.winner().map(|winner| winner.0),
+        rewound,
+    });
 }
 
 fn update_snake_head(
+    mut commands: Commands,
+    bundles: Res<PbrBundles>,
     draw_configuration: Res<DrawConfigurationResource>,
     snapshot: Res<SnakeGameSnapshot>,
-    mut head_snake_query: Query<&mut Transform, With<HeadSnakeComponent>>,
+    mut head_snake_query: Query<(Entity, &mut Transform, &HeadSnakeComponent)>,
 ) {
     if !snapshot.is_changed() {
         return;
     }
 
-    let position = &snapshot.snake[0];
-    move_to(
-        &mut head_snake_query.iter_mut().next().unwrap(),
-        position,
-        &draw_configuration,
-    );
+    for (player, snake) in snapshot.snakes.iter().enumerate() {
+        let head = head_snake_query
+            .iter_mut()
+            .find(|(_, _, head)| head.0 == player);
+
+        match (head, snake.body.first()) {
+            (Some((_, mut transform, _)), Some(position)) => {
+                move_to(&mut transform, position, &draw_configuration)
+            }
+            // Dead snakes leave the map
+            (Some((entity, _, _)), None) => commands.entity(entity).despawn_recursive(),
+            // Back after a restart or a rewind
+            (None, Some(position)) => spawn_snake_head(
+                &mut commands,
+                &bundles,
+                player,
+                position,
+                &draw_configuration,
+            ),
+            (None, None) => {}
+        }
+    }
 }
 
 fn update_snake_body(
@@ -142,34 +527,237 @@ fn update_snake_body(
     bundles: Res<PbrBundles>,
     draw_configuration: Res<DrawConfigurationResource>,
     snapshot: Res<SnakeGameSnapshot>,
-    mut body_snake_query: Query<(Entity, &mut Transform), With<BodySnakeComponent>>,
+    mut body_snake_query: Query<(Entity, &mut Transform, &BodySnakeComponent)>,
+    mut tail_cut_event_writer: EventWriter<TailCutEvent>,
 ) {
     if !snapshot.is_changed() {
         return;
     }
 
-    let mut bodies = body_snake_query.iter_mut();
-    for snake_body_position in snapshot.snake.iter().skip(1) {
-        match bodies.next() {
-            Some(mut e) => {
-                move_to(&mut e.1, snake_body_position, &draw_configuration);
-            }
-            None => {
-                spawn_snake_body(
-                    &mut commands,
-                    &bundles,
-                    snake_body_position,
-                    &draw_configuration,
-                );
+    for (player, snake) in snapshot.snakes.iter().enumerate() {
+        let pieces = body_snake_query
+            .iter_mut()
+            .filter(|(_, _, body)| body.0 == player)
+            .map(|(entity, transform, _)| (entity, transform));
+
+        // The remain pieces are the ones severed by a bite,
+        // or the whole body of a dead snake
+        sync_pieces(
+            &mut commands,
+            pieces,
+            snake.body.get(1..).unwrap_or_default(),
+            &draw_configuration,
+            |commands, position| {
+                spawn_snake_body(commands, &bundles, player, position, &draw_configuration)
+            },
+        );
+
+        if !snake.severed_tail.is_empty() {
+            tail_cut_event_writer.send(TailCutEvent {
+                player,
+                segments: snake.severed_tail.len(),
+            });
+        }
+    }
+}
+
+/// Picks the sprite of each piece, turned where the snake goes.
+/// The pieces are spawned a frame after the tick, so they are shaped every frame
+fn shape_snakes(
+    snake_game: Res<SnakeGame>,
+    snapshot: Res<SnakeGameSnapshot>,
+    mut head_snake_query: Query<(&mut Transform, &mut TextureAtlasSprite, &HeadSnakeComponent)>,
+    mut body_snake_query: Query<
+        (&mut Transform, &mut TextureAtlasSprite, &BodySnakeComponent),
+        Without<HeadSnakeComponent>,
+    >,
+) {
+    let dimension = snake_game.dimension();
+
+    for (player, snake) in snapshot.snakes.iter().enumerate() {
+        let head = head_snake_query
+            .iter_mut()
+            .filter(|(_, _, head)| head.0 == player)
+            .map(|(transform, sprite, _)| (transform, sprite));
+        let body = body_snake_query
+            .iter_mut()
+            .filter(|(_, _, body)| body.0 == player)
+            .map(|(transform, sprite, _)| (transform, sprite));
+
+        shape_pieces(head, body, &snake.shapes(dimension));
+    }
+}
+
+fn shape_ghost(
+    snake_game: Res<SnakeGame>,
+    snapshot: Res<SnakeGameSnapshot>,
+    ghost: Option<Res<GhostResource>>,
+    mut ghost_head_query: Query<
+        (&mut Transform, &mut TextureAtlasSprite),
+        With<GhostHeadComponent>,
+    >,
+    mut ghost_body_query: Query<(&mut Transform, &mut TextureAtlasSprite), GhostBody>,
+) {
+    let body = match ghost.as_ref().and_then(|ghost| ghost.0.get(snapshot.tick)) {
+        None => return,
+        Some(b) => b,
+    };
+
+    shape_pieces(
+        ghost_head_query.iter_mut(),
+        ghost_body_query.iter_mut(),
+        &piece_shapes(body, None, snake_game.dimension()),
+    );
+}
+
+/// In the order the pieces are placed
+fn shape_pieces<'a>(
+    mut head: impl Iterator<Item = (Mut<'a, Transform>, Mut<'a, TextureAtlasSprite>)>,
+    body: impl Iterator<Item = (Mut<'a, Transform>, Mut<'a, TextureAtlasSprite>)>,
+    shapes: &[PieceShape],
+) {
+    if let (Some((mut transform, mut sprite)), Some(shape)) = (head.next(), shapes.first()) {
+        shape_piece(&mut transform, &mut sprite, *shape);
+    }
+    for ((mut transform, mut sprite), shape) in body.zip(shapes.iter().skip(1)) {
+        shape_piece(&mut transform, &mut sprite, *shape);
+    }
+}
+
+fn shape_piece(transform: &mut Transform, sprite: &mut TextureAtlasSprite, shape: PieceShape) {
+    let (index, angle) = match shape {
+        PieceShape::Head(direction) => (HEAD_SPRITE, facing(direction)),
+        PieceShape::Straight(direction) => (STRAIGHT_SPRITE, facing(direction)),
+        PieceShape::Corner { from, to } => (CORNER_SPRITE, corner_angle(from, to)),
+        PieceShape::Tail(direction) => (TAIL_SPRITE, facing(direction)),
+    };
+
+    sprite.index = index;
+    transform.rotation = Quat::from_rotation_z(angle);
+}
+
+/// Turns a sprite facing up to the direction, counterclockwise
+fn facing(direction: Direction) -> f32 {
+    match direction {
+        Direction::Up => 0.,
+        Direction::Left => FRAC_PI_2,
+        Direction::Down => PI,
+        Direction::Right => -FRAC_PI_2,
+    }
+}
+
+/// Turns the corner sprite to join the side the piece is entered from and the one it is left to
+fn corner_angle(from: Direction, to: Direction) -> f32 {
+    let sides = [from.opposite(), to];
+    let joins = |a, b| sides.contains(&a) && sides.contains(&b);
+
+    if joins(Direction::Down, Direction::Right) {
+        0.
+    } else if joins(Direction::Right, Direction::Up) {
+        FRAC_PI_2
+    } else if joins(Direction::Up, Direction::Left) {
+        PI
+    } else {
+        -FRAC_PI_2
+    }
+}
+
+/// The snapshots to slide the snakes between
+fn remember_snapshot(
+    snapshot: Res<SnakeGameSnapshot>,
+    mut interpolation: ResMut<InterpolationResource>,
+) {
+    if !snapshot.is_changed() {
+        return;
+    }
+
+    // Restarts and rewinds jump
+    let followed = snapshot.tick == interpolation.to.tick + 1;
+    let previous = std::mem::replace(&mut interpolation.to, snapshot.clone());
+    interpolation.from = if followed { previous } else { snapshot.clone() };
+}
+
+/// Slides the pieces from their previous cells, as the tick timer goes.
+/// Each piece takes the cell of the one ahead of it, so the pieces are placed by index.
+/// Paused or over, the game shows the snakes where they are
+fn interpolate_snakes(
+    settings: Res<SettingsResource>,
+    game_timers: Res<GameTimerResource>,
+    draw_configuration: Res<DrawConfigurationResource>,
+    snake_game: Res<SnakeGame>,
+    mut interpolation: ResMut<InterpolationResource>,
+    mut head_snake_query: Query<(&mut Transform, &HeadSnakeComponent)>,
+    mut body_snake_query: Query<(&mut Transform, &BodySnakeComponent), Without<HeadSnakeComponent>>,
+) {
+    if !settings.settings.smooth_movement {
+        return;
+    }
+
+    let progress = if game_timers.0.paused() {
+        // On resume, the snakes wait there for the next tick instead of sliding back
+        if interpolation.from.tick != interpolation.to.tick {
+            interpolation.from = interpolation.to.clone();
+        }
+        1.
+    } else {
+        game_timers.0.percent()
+    };
+    let dimension = snake_game.dimension();
+    let slide = |transform: &mut Transform, from: Option<&Position>, to: &Position| {
+        let at = interpolate(from, to, progress, dimension, &draw_configuration);
+        transform.translation.x = at.x;
+        transform.translation.y = at.y;
+    };
+
+    for (player, snake) in interpolation.to.snakes.iter().enumerate() {
+        let from = interpolation
+            .from
+            .snakes
+            .get(player)
+            .map(|snake| snake.body.as_slice())
+            .unwrap_or_default();
+
+        for (mut transform, _) in head_snake_query
+            .iter_mut()
+            .filter(|(_, head)| head.0 == player)
+        {
+            if let Some(head) = snake.body.first() {
+                slide(&mut transform, from.first(), head);
             }
         }
+
+        let pieces = body_snake_query
+            .iter_mut()
+            .filter(|(_, body)| body.0 == player);
+        for (index, ((mut transform, _), to)) in pieces.zip(snake.body.iter().skip(1)).enumerate() {
+            // A new piece stays where the tail was
+            slide(&mut transform, from.get(index + 1), to);
+        }
     }
+}
 
-    // The remain pieces are unknown: we should remove then
-    // Theoretically there're not remains
-    bodies.for_each(|p| {
-        commands.entity(p.0).despawn();
-    });
+/// Where a piece going `from` a cell `to` the next is, `progress` of the way.
+/// Through the walls it comes in from the other side
+fn interpolate(
+    from: Option<&Position>,
+    to: &Position,
+    progress: f32,
+    dimension: (usize, usize),
+    draw_configuration: &DrawConfigurationResource,
+) -> Vec2 {
+    let to = cell_center(to, draw_configuration);
+    let from = from.map_or(to, |from| cell_center(from, draw_configuration));
+
+    let board = Vec2::new(dimension.0 as f32, dimension.1 as f32) * draw_configuration.cell_size;
+    let mut step = to - from;
+    if step.x.abs() > board.x / 2. {
+        step.x -= board.x.copysign(step.x);
+    }
+    if step.y.abs() > board.y / 2. {
+        step.y -= board.y.copysign(step.y);
+    }
+
+    to - step * (1. - progress)
 }
 
 fn update_food(
@@ -185,9 +773,271 @@ fn update_food(
     let mut food_position = food_query.iter_mut().next().unwrap();
     move_to(&mut food_position, &snapshot.food, &draw_configuration);
 
-    if snapshot.food_ate {
-        food_ate_event_writer.send(FoodAteEvent);
+    for (player, snake) in snapshot.snakes.iter().enumerate() {
+        if let Some(kind) = snake.food_ate {
+            food_ate_event_writer.send(FoodAteEvent {
+                player,
+                kind,
+                position: snake.body[0].clone(),
+                length: snake.body.len(),
+                tick: snapshot.tick,
+            });
+        }
+    }
+}
+
+fn update_temporary_walls(
+    mut commands: Commands,
+    bundles: Res<PbrBundles>,
+    draw_configuration: Res<DrawConfigurationResource>,
+    snapshot: Res<SnakeGameSnapshot>,
+    mut temporary_wall_query: Query<(Entity, &mut Transform), With<TemporaryWallComponent>>,
+) {
+    if !snapshot.is_changed() {
+        return;
+    }
+
+    sync_pieces(
+        &mut commands,
+        temporary_wall_query.iter_mut(),
+        &snapshot.temporary_walls,
+        &draw_configuration,
+        |commands, position| {
+            let mut wall = bundles.temporary_wall();
+            move_to(&mut wall.transform, position, &draw_configuration);
+            wall.transform.translation.z = WALL_Z;
+
+            commands.spawn_bundle(wall).insert(TemporaryWallComponent);
+        },
+    );
+}
+
+fn update_temporary_food(
+    mut commands: Commands,
+    bundles: Res<PbrBundles>,
+    draw_configuration: Res<DrawConfigurationResource>,
+    snapshot: Res<SnakeGameSnapshot>,
+    mut temporary_food_query: Query<(Entity, &mut Transform), With<TemporaryFoodComponent>>,
+) {
+    if !snapshot.is_changed() {
+        return;
+    }
+
+    sync_pieces(
+        &mut commands,
+        temporary_food_query.iter_mut(),
+        &snapshot.temporary_food,
+        &draw_configuration,
+        |commands, position| {
+            let mut food = bundles.food();
+            move_to(&mut food.transform, position, &draw_configuration);
+            food.transform.translation.z = FOOD_Z;
+
+            commands.spawn_bundle(food).insert(TemporaryFoodComponent);
+        },
+    );
+}
+
+/// The ghost body, the head aside
+type GhostBody = (With<GhostBodyComponent>, Without<GhostHeadComponent>);
+
+/// The ghost follows the tick of the game, rewinds included
+fn update_ghost(
+    mut commands: Commands,
+    bundles: Res<PbrBundles>,
+    draw_configuration: Res<DrawConfigurationResource>,
+    snapshot: Res<SnakeGameSnapshot>,
+    ghost: Option<Res<GhostResource>>,
+    mut ghost_head_query: Query<(Entity, &mut Transform), With<GhostHeadComponent>>,
+    mut ghost_body_query: Query<(Entity, &mut Transform), GhostBody>,
+) {
+    let ghost = match ghost {
+        None => return,
+        Some(g) => g,
+    };
+    if !snapshot.is_changed() && !ghost.is_changed() {
+        return;
+    }
+
+    // The ghost run is over: it leaves the map
+    let body = ghost
+        .0
+        .get(snapshot.tick)
+        .map(Vec::as_slice)
+        .unwrap_or_default();
+    let head = body.get(..1).unwrap_or_default();
+    let body = body.get(1..).unwrap_or_default();
+
+    sync_pieces(
+        &mut commands,
+        ghost_head_query.iter_mut(),
+        head,
+        &draw_configuration,
+        |commands, position| {
+            let mut ghost_head = bundles.ghost_head();
+            move_to(&mut ghost_head.transform, position, &draw_configuration);
+            ghost_head.transform.translation.z = GHOST_Z;
+
+            commands.spawn_bundle(ghost_head).insert(GhostHeadComponent);
+        },
+    );
+    sync_pieces(
+        &mut commands,
+        ghost_body_query.iter_mut(),
+        body,
+        &draw_configuration,
+        |commands, position| {
+            let mut ghost_body = bundles.ghost_body();
+            move_to(&mut ghost_body.transform, position, &draw_configuration);
+            ghost_body.transform.translation.z = GHOST_Z;
+
+            commands.spawn_bundle(ghost_body).insert(GhostBodyComponent);
+        },
+    );
+}
+
+/// Moves the already spawned pieces to the given positions,
+/// spawning the missing ones and despawning the ones in excess
+fn sync_pieces<'a>(
+    commands: &mut Commands,
+    mut pieces: impl Iterator<Item = (Entity, Mut<'a, Transform>)>,
+    positions: &[Position],
+    draw_configuration: &DrawConfigurationResource,
+    mut spawn: impl FnMut(&mut Commands, &Position),
+) {
+    for position in positions {
+        match pieces.next() {
+            Some((_, mut transform)) => move_to(&mut transform, position, draw_configuration),
+            None => spawn(commands, position),
+        }
     }
+
+    pieces.for_each(|(entity, _)| {
+        commands.entity(entity).despawn_recursive();
+    });
+}
+
+/// Everything drawn on the board
+type BoardPieces = Or<(
+    With<WallComponent>,
+    With<TemporaryWallComponent>,
+    With<FoodComponent>,
+    With<TemporaryFoodComponent>,
+    With<HeadSnakeComponent>,
+    With<BodySnakeComponent>,
+    With<GhostHeadComponent>,
+    With<GhostBodyComponent>,
+)>;
+
+/// Fits the board in the resized window, or to the minimum cell size of the settings,
+/// moving and scaling what is drawn on it
+fn relayout_board(
+    mut window_resized_event_reader: EventReader<WindowResized>,
+    windows: Res<Windows>,
+    settings: Res<SettingsResource>,
+    snake_game: Res<SnakeGame>,
+    mut draw_configuration: ResMut<DrawConfigurationResource>,
+    mut bundles: ResMut<PbrBundles>,
+    mut piece_query: Query<
+        (
+            &mut Transform,
+            Option<&mut Sprite>,
+            Option<&mut TextureAtlasSprite>,
+        ),
+        BoardPieces,
+    >,
+) {
+    let resized = window_resized_event_reader.iter().count() > 0;
+    if !resized && !settings.is_changed() {
+        return;
+    }
+    let window = match windows.get_primary() {
+        None => return,
+        Some(w) => w,
+    };
+
+    let previous = draw_configuration.clone();
+    let fitted = fit_board(
+        Vec2::new(window.width(), window.height()),
+        previous.margins,
+        settings.settings.min_cell_size,
+        snake_game.dimension(),
+    );
+    if fitted == previous {
+        return;
+    }
+    *draw_configuration = fitted;
+    bundles.resize(draw_configuration.cell_size);
+
+    let cell = Vec2::splat(draw_configuration.cell_size);
+    let scale = draw_configuration.cell_size / previous.cell_size;
+    let previous_origin = Vec2::new(previous.origin.0, previous.origin.1);
+    let origin = Vec2::new(draw_configuration.origin.0, draw_configuration.origin.1);
+    for (mut transform, sprite, atlas_sprite) in piece_query.iter_mut() {
+        // The pieces sliding between the cells stay the same way along
+        let at = (transform.translation.truncate() - previous_origin) * scale + origin;
+        transform.translation.x = at.x;
+        transform.translation.y = at.y;
+
+        if let Some(mut sprite) = sprite {
+            sprite.custom_size = Some(cell);
+        }
+        if let Some(mut sprite) = atlas_sprite {
+            sprite.custom_size = Some(cell);
+        }
+    }
+}
+
+/// The biggest square cells fitting the window without the margins, the board in the middle of the rest.
+/// Not smaller than the minimum size if any: the board goes past the window, followed by the camera
+fn fit_board(
+    window: Vec2,
+    margins: UiRect<f32>,
+    min_cell_size: Option<u32>,
+    dimension: (usize, usize),
+) -> DrawConfigurationResource {
+    let min = Vec2::new(
+        -window.x / 2. + margins.left,
+        -window.y / 2. + margins.bottom,
+    );
+    let max = Vec2::new(window.x / 2. - margins.right, window.y / 2. - margins.top);
+
+    let cells = Vec2::new(dimension.0 as f32, dimension.1 as f32);
+    let room = (max - min) / cells;
+    // Never upside down in a tiny window
+    let min_cell_size = min_cell_size.map_or(1., |size| size as f32);
+    let cell_size = room.x.min(room.y).max(min_cell_size);
+    let origin = (min + max) / 2. - cells * cell_size / 2.;
+
+    DrawConfigurationResource {
+        cell_size,
+        half_cell: cell_size / 2.,
+        origin: (origin.x, origin.y),
+        margins,
+    }
+}
+
+/// Follows the foods eaten, restarts and rewinds included
+fn speed_up(
+    snake_game: Res<SnakeGame>,
+    snapshot: Res<SnakeGameSnapshot>,
+    mut game_timers: ResMut<GameTimerResource>,
+) {
+    if !snapshot.is_changed() {
+        return;
+    }
+
+    let foods = snake_game.stats().iter().map(|stats| stats.foods).sum();
+    let duration = tick_duration(foods);
+    if game_timers.0.duration() != duration {
+        game_timers.0.set_duration(duration);
+    }
+}
+
+fn tick_duration(foods: usize) -> Duration {
+    let speed_ups = (foods / SPEED_UP_FOODS) as i32;
+    let seconds = TICK_SECONDS * SPEED_UP_FACTOR.powi(speed_ups);
+    Duration::from_secs_f32(seconds.max(MIN_TICK_SECONDS))
 }
 
 fn game_over(
@@ -202,25 +1052,132 @@ fn game_over(
     game_timers.pause();
 }
 
+/// Follows the theme chosen in the settings
+fn switch_theme(
+    settings: Res<SettingsResource>,
+    asset_server: Res<AssetServer>,
+    mut texture_atlases: ResMut<Assets<TextureAtlas>>,
+    draw_configuration: Res<DrawConfigurationResource>,
+    mut theme: ResMut<ThemeResource>,
+    mut bundles: ResMut<PbrBundles>,
+    mut theme_changed_event_writer: EventWriter<ThemeChangedEvent>,
+) {
+    if !settings.is_changed() {
+        return;
+    }
+
+    let new_theme = find_theme(&theme.themes, &settings.settings.theme);
+    if *new_theme == theme.theme {
+        return;
+    }
+
+    info!("Theme: {}", new_theme.name);
+    let new_theme = new_theme.clone();
+    *bundles = PbrBundles::new(
+        &new_theme,
+        &asset_server,
+        &mut texture_atlases,
+        &draw_configuration,
+    );
+    theme.font = asset_server.load(&new_theme.font);
+    theme.theme = new_theme;
+
+    theme_changed_event_writer.send(ThemeChangedEvent);
+}
+
+type Walls = Or<(With<WallComponent>, With<TemporaryWallComponent>)>;
+
+type Foods = Or<(With<FoodComponent>, With<TemporaryFoodComponent>)>;
+
+/// The textures of the walls, then of the foods
+type BoardTextures<'w, 's> = ParamSet<
+    'w,
+    's,
+    (
+        Query<'w, 's, &'static mut Handle<Image>, Walls>,
+        Query<'w, 's, &'static mut Handle<Image>, Foods>,
+    ),
+>;
+
+/// The snakes and the ghost
+type SnakePieces = Or<(
+    With<HeadSnakeComponent>,
+    With<BodySnakeComponent>,
+    With<GhostHeadComponent>,
+    With<GhostBodyComponent>,
+)>;
+
+/// The sprites already spawned take the textures of the new theme
+fn retheme_sprites(
+    mut theme_changed_event_reader: EventReader<ThemeChangedEvent>,
+    bundles: Res<PbrBundles>,
+    theme: Res<ThemeResource>,
+    mut clear_color: ResMut<ClearColor>,
+    mut textures: BoardTextures,
+    mut texture_atlas_query: Query<&mut Handle<TextureAtlas>, SnakePieces>,
+) {
+    if theme_changed_event_reader.iter().count() == 0 {
+        return;
+    }
+
+    clear_color.0 = rgb(theme.theme.background);
+    for mut texture in textures.p0().iter_mut() {
+        *texture = bundles.wall.texture.clone();
+    }
+    for mut texture in textures.p1().iter_mut() {
+        *texture = bundles.food.texture.clone();
+    }
+    for mut texture_atlas in texture_atlas_query.iter_mut() {
+        *texture_atlas = bundles.snake_head.texture_atlas.clone();
+    }
+}
+
+/// The texts already spawned take the font and the colours of the new theme
+fn retheme_texts(
+    mut theme_changed_event_reader: EventReader<ThemeChangedEvent>,
+    theme: Res<ThemeResource>,
+    mut text_query: Query<(&mut Text, &ThemedTextComponent)>,
+) {
+    if theme_changed_event_reader.iter().count() == 0 {
+        return;
+    }
+
+    for (mut text, themed_text) in text_query.iter_mut() {
+        for (section, color) in text.sections.iter_mut().zip(&themed_text.0) {
+            section.style.font = theme.font.clone();
+            section.style.color = theme.color(*color);
+        }
+    }
+}
+
 fn draw_snake(
     mut commands: Commands,
     bundles: Res<PbrBundles>,
     draw_configuration: Res<DrawConfigurationResource>,
     snapshot: Res<SnakeGameSnapshot>,
 ) {
-    for (i, position) in snapshot.snake.iter().enumerate() {
-        let is_head = i == 0;
-
-        if is_head {
-            let mut snake_head = bundles.snake_head();
+    for (player, snake) in snapshot.snakes.iter().enumerate() {
+        for (i, position) in snake.body.iter().enumerate() {
+            let is_head = i == 0;
 
-            move_to(&mut snake_head.transform, position, &draw_configuration);
-            snake_head.transform.translation.z = SNAKE_Z;
-
-            commands.spawn_bundle(snake_head).insert(HeadSnakeComponent);
-        } else {
-            spawn_snake_body(&mut commands, &bundles, position, &draw_configuration);
-        };
+            if is_head {
+                spawn_snake_head(
+                    &mut commands,
+                    &bundles,
+                    player,
+                    position,
+                    &draw_configuration,
+                );
+            } else {
+                spawn_snake_body(
+                    &mut commands,
+                    &bundles,
+                    player,
+                    position,
+                    &draw_configuration,
+                );
+            };
+        }
     }
 }
 
@@ -249,7 +1206,7 @@ fn draw_field(
             );
             wall.transform.translation.z = WALL_Z;
 
-            commands.spawn_bundle(wall);
+            commands.spawn_bundle(wall).insert(WallComponent);
         }
     }
 
@@ -262,76 +1219,166 @@ fn draw_field(
     commands.spawn_bundle(food).insert(FoodComponent);
 }
 
+fn spawn_snake_head(
+    commands: &mut Commands,
+    bundles: &PbrBundles,
+    player: usize,
+    position: &Position,
+    draw_configuration: &DrawConfigurationResource,
+) {
+    let mut snake_head = bundles.snake_head(player);
+    move_to(&mut snake_head.transform, position, draw_configuration);
+    snake_head.transform.translation.z = SNAKE_Z;
+
+    commands
+        .spawn_bundle(snake_head)
+        .insert(HeadSnakeComponent(player));
+}
+
 fn spawn_snake_body(
     commands: &mut Commands,
     bundles: &PbrBundles,
+    player: usize,
     position: &Position,
     draw_configuration: &DrawConfigurationResource,
 ) {
-    let mut sprite = bundles.snake_body();
+    let mut sprite = bundles.snake_body(player);
     move_to(&mut sprite.transform, position, draw_configuration);
     // Force snake be rendered over the walls
     sprite.transform.translation.z = SNAKE_Z;
 
-    commands.spawn_bundle(sprite).insert(BodySnakeComponent);
+    commands
+        .spawn_bundle(sprite)
+        .insert(BodySnakeComponent(player));
 }
 
-fn move_to(
+pub fn move_to(
     transform: &mut Transform,
     to: &Position,
     draw_configuration: &DrawConfigurationResource,
 ) {
-    transform.translation.x = to.x as f32 * draw_configuration.cell_size
-        + draw_configuration.origin.0
-        + draw_configuration.half_cell;
-    transform.translation.y = to.y as f32 * draw_configuration.cell_size
-        + draw_configuration.origin.1
-        + draw_configuration.half_cell;
+    let center = cell_center(to, draw_configuration);
+    transform.translation.x = center.x;
+    transform.translation.y = center.y;
+}
+
+fn cell_center(position: &Position, draw_configuration: &DrawConfigurationResource) -> Vec2 {
+    Vec2::new(
+        position.x as f32 * draw_configuration.cell_size
+            + draw_configuration.origin.0
+            + draw_configuration.half_cell,
+        position.y as f32 * draw_configuration.cell_size
+            + draw_configuration.origin.1
+            + draw_configuration.half_cell,
+    )
 }
 
 pub struct PbrBundles {
     wall: SpriteBundle,
-    snake_head: SpriteBundle,
-    snake_body: SpriteBundle,
+    temporary_wall: SpriteBundle,
+    snake_head: SpriteSheetBundle,
+    snake_body: SpriteSheetBundle,
     food: SpriteBundle,
 }
 impl PbrBundles {
     pub fn wall(&self) -> SpriteBundle {
         self.wall.clone()
     }
-    pub fn snake_body(&self) -> SpriteBundle {
-        self.snake_body.clone()
+    pub fn temporary_wall(&self) -> SpriteBundle {
+        self.temporary_wall.clone()
+    }
+    pub fn snake_body(&self, player: usize) -> SpriteSheetBundle {
+        with_player_color(self.snake_body.clone(), player)
     }
-    pub fn snake_head(&self) -> SpriteBundle {
-        self.snake_head.clone()
+    pub fn snake_head(&self, player: usize) -> SpriteSheetBundle {
+        with_player_color(self.snake_head.clone(), player)
     }
     pub fn food(&self) -> SpriteBundle {
         self.food.clone()
     }
-}
-impl FromWorld for PbrBundles {
-    fn from_world(world: &mut World) -> Self {
-        let draw_configuration = world.resource::<DrawConfigurationResource>().clone();
+    pub fn ghost_head(&self) -> SpriteSheetBundle {
+        with_ghost_color(self.snake_head.clone())
+    }
+    pub fn ghost_body(&self) -> SpriteSheetBundle {
+        with_ghost_color(self.snake_body.clone())
+    }
 
-        let mut asset_server = world.resource_mut::<AssetServer>();
+    /// For the sprites spawned from now on
+    fn resize(&mut self, cell_size: f32) {
+        let size = Some(Vec2::splat(cell_size));
+        self.wall.sprite.custom_size = size;
+        self.temporary_wall.sprite.custom_size = size;
+        self.food.sprite.custom_size = size;
+        self.snake_head.sprite.custom_size = size;
+        self.snake_body.sprite.custom_size = size;
+    }
+}
+impl PbrBundles {
+    fn new(
+        theme: &Theme,
+        asset_server: &AssetServer,
+        texture_atlases: &mut Assets<TextureAtlas>,
+        draw_configuration: &DrawConfigurationResource,
+    ) -> Self {
+        let wall = load_sprite(asset_server, &theme.wall, draw_configuration);
+        let mut temporary_wall = wall.clone();
+        temporary_wall.sprite.color = Color::rgba(1., 1., 1., 0.5);
+        let food = load_sprite(asset_server, &theme.food, draw_configuration);
 
-        let wall = load_sprite(&mut asset_server, "wall.png", &draw_configuration);
-        let snake_body = load_sprite(&mut asset_server, "snake_body.png", &draw_configuration);
-        let snake_head = load_sprite(&mut asset_server, "snake_head.png", &draw_configuration);
-        let food = load_sprite(&mut asset_server, "food.png", &draw_configuration);
+        let snake_atlas = texture_atlases.add(TextureAtlas::from_grid(
+            asset_server.load(&theme.snake),
+            Vec2::splat(SNAKE_SPRITE_SIZE),
+            SNAKE_SPRITES,
+            1,
+        ));
+        let snake_head = load_snake_sprite(&snake_atlas, HEAD_SPRITE, draw_configuration);
+        let snake_body = load_snake_sprite(&snake_atlas, STRAIGHT_SPRITE, draw_configuration);
 
         PbrBundles {
             wall,
+            temporary_wall,
             snake_body,
             snake_head,
             food,
         }
     }
 }
+impl FromWorld for PbrBundles {
+    fn from_world(world: &mut World) -> Self {
+        let draw_configuration = world.resource::<DrawConfigurationResource>().clone();
+        let theme = world.resource::<ThemeResource>().theme.clone();
+        let asset_server = world.resource::<AssetServer>().clone();
+        let mut texture_atlases = world.resource_mut::<Assets<TextureAtlas>>();
+
+        PbrBundles::new(
+            &theme,
+            &asset_server,
+            &mut texture_atlases,
+            &draw_configuration,
+        )
+    }
+}
+
+/// Tints the snake sprites, so the players can tell their snakes apart
+fn with_player_color(mut sprite: SpriteSheetBundle, player: usize) -> SpriteSheetBundle {
+    sprite.sprite.color = match player {
+        0 => Color::WHITE,
+        1 => Color::rgb(1.0, 0.6, 0.6),
+        2 => Color::rgb(0.6, 0.6, 1.0),
+        _ => Color::rgb(0.6, 1.0, 0.6),
+    };
+    sprite
+}
+
+/// Translucent, so the ghost is never taken for a live snake
+fn with_ghost_color(mut sprite: SpriteSheetBundle) -> SpriteSheetBundle {
+    sprite.sprite.color = Color::rgba(1., 1., 1., 0.3);
+    sprite
+}
 
 fn load_sprite(
-    asset_server: &mut AssetServer,
-    s: &'static str,
+    asset_server: &AssetServer,
+    s: &str,
     draw_configuration: &DrawConfigurationResource,
 ) -> SpriteBundle {
     let cell_size = draw_configuration.cell_size;
@@ -345,39 +1392,323 @@ fn load_sprite(
     }
 }
 
-mod resources {
-    use bevy::time::Timer;
+fn rgb(color: [f32; 3]) -> Color {
+    Color::rgb(color[0], color[1], color[2])
+}
+
+fn load_snake_sprite(
+    atlas: &Handle<TextureAtlas>,
+    index: usize,
+    draw_configuration: &DrawConfigurationResource,
+) -> SpriteSheetBundle {
+    let cell_size = draw_configuration.cell_size;
+    SpriteSheetBundle {
+        sprite: TextureAtlasSprite {
+            index,
+            custom_size: Some(Vec2::new(cell_size, cell_size)),
+            ..default()
+        },
+        texture_atlas: atlas.clone(),
+        ..default()
+    }
+}
+
+pub mod resources {
+    use std::{
+        collections::{HashMap, VecDeque},
+        time::Duration,
+    };
+
+    use bevy::{
+        prelude::{Color, Font, Handle},
+        text::TextStyle,
+        time::Timer,
+        ui::UiRect,
+    };
 
-    #[derive(Clone)]
+    use crate::{
+        configuration::Settings,
+        snake::{Direction, Position, SnakeGame, SnakeGameSnapshot, SnakeId, Turn},
+        themes::Theme,
+    };
+
+    use super::components::ThemeColor;
+
+    #[derive(Clone, PartialEq)]
     pub struct DrawConfigurationResource {
         pub half_cell: f32,
         pub cell_size: f32,
+        /// The bottom left corner of the board
         pub origin: (f32, f32),
+        /// Kept when the window is resized
+        pub margins: UiRect<f32>,
     }
 
     pub struct GameTimerResource(pub Timer);
+
+    /// How long the current game has been played, paused time aside
+    #[derive(Default)]
+    pub struct GameClockResource {
+        pub played: Duration,
+        /// The time played at each tick of the history, the last one on the back
+        pub ticks: VecDeque<Duration>,
+    }
+
+    impl GameClockResource {
+        pub fn tick(&mut self) {
+            self.ticks.push_back(self.played);
+            if self.ticks.len() > super::REWIND_TICKS {
+                self.ticks.pop_front();
+            }
+        }
+
+        /// Back to the time of the last tick, as the game
+        pub fn rewind(&mut self) {
+            if let Some(played) = self.ticks.pop_back() {
+                self.played = played;
+            }
+        }
+    }
+
+    /// The direction each player wants to go to
+    pub struct DirectionsResource(pub HashMap<SnakeId, Direction>);
+
+    /// The snakes steered from this keyboard
+    pub struct LocalPlayersResource(pub Vec<SnakeId>);
+
+    /// The turns asked by the players, played on the next tick
+    #[derive(Default)]
+    pub struct TurnsResource(pub HashMap<SnakeId, Turn>);
+
+    pub struct ThemeResource {
+        pub themes: Vec<Theme>,
+        pub theme: Theme,
+        pub font: Handle<Font>,
+    }
+
+    impl ThemeResource {
+        pub fn text_color(&self) -> Color {
+            super::rgb(self.theme.text)
+        }
+
+        pub fn highlight_color(&self) -> Color {
+            super::rgb(self.theme.highlight)
+        }
+
+        pub fn color(&self, color: ThemeColor) -> Color {
+            match color {
+                ThemeColor::Text => self.text_color(),
+                ThemeColor::Highlight => self.highlight_color(),
+            }
+        }
+
+        /// In the font and the text colour of the theme
+        pub fn text_style(&self, font_size: f32) -> TextStyle {
+            TextStyle {
+                font: self.font.clone(),
+                font_size,
+                color: self.text_color(),
+            }
+        }
+    }
+
+    pub struct SettingsResource {
+        pub settings: Settings,
+        /// The screens taking the keys, like the settings or the name prompt:
+        /// each one counts itself in while open
+        pub editors: usize,
+    }
+
+    impl SettingsResource {
+        /// The keys do nothing else and the game waits
+        pub fn editing(&self) -> bool {
+            self.editors > 0
+        }
+    }
+
+    /// The snapshots before and after the last tick
+    pub struct InterpolationResource {
+        pub from: SnakeGameSnapshot,
+        pub to: SnakeGameSnapshot,
+    }
+
+    /// A run replayed by a translucent snake, colliding with nothing.
+    /// Its body at each tick, head first
+    pub struct GhostResource(pub Vec<Vec<Position>>);
+
+    /// The games to go back to
+    pub struct HistoryResource {
+        /// The foods are the same on restart, else new
+        pub seed: Option<u64>,
+        pub initial: SnakeGame,
+        /// The game before each tick, the last one on the back
+        pub ticks: VecDeque<SnakeGame>,
+        /// Went back since the start: no high score, ghost or achievement
+        pub rewound: bool,
+    }
 }
 
 pub mod events {
-    use crate::snake::SnakeError;
+    use crate::{
+        configuration::Action,
+        snake::{Direction, FoodKind, Position, SnakeError},
+    };
 
     pub struct GameTick;
 
+    /// A local player turns, from any input device
+    pub struct SteerEvent {
+        /// The index in the local players
+        pub player: usize,
+        pub direction: Direction,
+    }
+
+    /// An action on the whole game, not steering a snake
+    pub struct GameActionEvent(pub Action);
+
+    /// The game is back to the beginning
+    pub struct RestartEvent;
+
+    /// The game is back a tick
+    pub struct RewindEvent;
+
     pub struct GameOverEvent {
-        pub error: SnakeError,
+        /// The players died in the last tick, with the reason
+        pub errors: Vec<(usize, SnakeError)>,
+        /// The last player standing, if more than one played
+        pub winner: Option<usize>,
+        /// The game went back: no record is kept
+        pub rewound: bool,
+    }
+    pub struct FoodAteEvent {
+        pub player: usize,
+        pub kind: FoodKind,
+        /// Where the head is
+        pub position: Position,
+        /// The snake length, the food not counted yet
+        pub length: usize,
+        /// The tick of the game the food was eaten in
+        pub tick: usize,
+    }
+
+    /// A snake went a cell further, alive
+    pub struct SnakeMovedEvent {
+        pub player: usize,
+        /// The snake follows a new direction
+        pub turned: bool,
+        pub length: usize,
+    }
+
+    /// The theme in the settings is another one
+    pub struct ThemeChangedEvent;
+
+    pub struct TailCutEvent {
+        pub player: usize,
+        pub segments: usize,
     }
-    pub struct FoodAteEvent;
 }
 
-mod components {
+pub mod components {
     use bevy::prelude::Component;
 
+    /// The colours of the theme the texts are drawn with
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum ThemeColor {
+        Text,
+        Highlight,
+    }
+
+    /// The colour of each section of the text: it follows the theme, with its font
+    #[derive(Component)]
+    pub struct ThemedTextComponent(pub Vec<ThemeColor>);
+
+    /// The player the snake belongs to
+    #[derive(Component)]
+    pub struct HeadSnakeComponent(pub usize);
+
+    #[derive(Component)]
+    pub struct BodySnakeComponent(pub usize);
+
     #[derive(Component)]
-    pub struct HeadSnakeComponent;
+    pub struct GhostHeadComponent;
 
     #[derive(Component)]
-    pub struct BodySnakeComponent;
+    pub struct GhostBodyComponent;
+
+    #[derive(Component)]
+    pub struct WallComponent;
 
     #[derive(Component)]
     pub struct FoodComponent;
+
+    #[derive(Component)]
+    pub struct TemporaryWallComponent;
+
+    #[derive(Component)]
+    pub struct TemporaryFoodComponent;
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use bevy::{math::Vec2, ui::UiRect};
+
+    use super::{fit_board, resources::GameClockResource, tick_duration};
+
+    #[test]
+    fn test_board_should_be_centered_under_the_hud() {
+        let margins = UiRect {
+            top: 30.,
+            bottom: 5.,
+            right: 5.,
+            left: 5.,
+        };
+
+        // Wider than tall: the board is in the middle
+        let draw_configuration = fit_board(Vec2::new(600., 335.), margins, None, (10, 10));
+        assert_eq!(draw_configuration.cell_size, 30.);
+        assert_eq!(draw_configuration.origin, (-150., -162.5));
+
+        // Taller than wide: the board is between the HUD and the bottom
+        let draw_configuration = fit_board(Vec2::new(110., 600.), margins, None, (10, 10));
+        assert_eq!(draw_configuration.cell_size, 10.);
+        assert_eq!(draw_configuration.origin, (-50., -62.5));
+
+        // Past the window with a minimum cell size, still centered
+        let draw_configuration = fit_board(Vec2::new(110., 600.), margins, Some(24), (10, 10));
+        assert_eq!(draw_configuration.cell_size, 24.);
+        assert_eq!(draw_configuration.origin, (-120., -132.5));
+    }
+
+    #[test]
+    fn test_ticks_should_get_shorter_with_the_foods() {
+        assert_eq!(tick_duration(0), Duration::from_secs_f32(0.5));
+        assert_eq!(tick_duration(4), Duration::from_secs_f32(0.5));
+        assert_eq!(tick_duration(5), Duration::from_secs_f32(0.45));
+        assert_eq!(tick_duration(12), tick_duration(10));
+        assert!(tick_duration(10) < tick_duration(5));
+        // Not too fast to be played
+        assert_eq!(tick_duration(500), Duration::from_secs_f32(0.15));
+    }
+
+    #[test]
+    fn test_clock_should_go_back_with_the_rewinds() {
+        let mut clock = GameClockResource {
+            played: Duration::from_secs(1),
+            ..GameClockResource::default()
+        };
+        clock.tick();
+        clock.played = Duration::from_secs(2);
+        clock.tick();
+        clock.played = Duration::from_millis(2200);
+
+        clock.rewind();
+        assert_eq!(clock.played, Duration::from_secs(2));
+        clock.rewind();
+        assert_eq!(clock.played, Duration::from_secs(1));
+        // Back to the beginning
+        clock.rewind();
+        assert_eq!(clock.played, Duration::from_secs(1));
+    }
 }
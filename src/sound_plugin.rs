@@ -0,0 +1,199 @@
+use std::time::Duration;
+
+use bevy::{audio::AudioSink, prelude::*};
+
+use crate::{
+    loading_plugin::resources::LoadingResource,
+    settings_plugin::resources::SettingsScreenResource,
+    snake::SnakeGameSnapshot,
+    snake_plugin::{
+        events::{FoodAteEvent, GameOverEvent, SnakeMovedEvent},
+        resources::{GameTimerResource, SettingsResource},
+    },
+};
+
+use self::resources::{MusicResource, SoundsResource};
+
+/// The music goes this faster for every piece of the longest snake
+const MUSIC_SPEED_STEP: f32 = 0.02;
+const MUSIC_MAX_SPEED: f32 = 1.5;
+
+/// Sounds on eating, turning, speeding up, game over and in the settings,
+/// with a music going faster as the snakes grow. At the volumes of the settings.
+/// Silent without `AudioPlugin`. Needs `LoadingPlugin`, that waits for the sounds,
+/// `SnakePlugin` and `SettingsPlugin`
+pub struct SoundPlugin;
+
+impl Plugin for SoundPlugin {
+    fn build(&self, app: &mut App) {
+        // A missing audio device only makes `AudioPlugin` warn
+        if app.world.get_resource::<Audio>().is_none() {
+            warn!("No audio: the game is silent");
+            return;
+        }
+
+        let asset_server = app.world.resource::<AssetServer>();
+        let mut files = vec![];
+        let mut load = |path: &str| {
+            let handle: Handle<AudioSource> = asset_server.load(path);
+            files.push(path.to_string());
+            handle
+        };
+        let sounds = SoundsResource {
+            eat: load("sounds/eat.wav"),
+            turn: load("sounds/turn.wav"),
+            speed_up: load("sounds/speed_up.wav"),
+            game_over: load("sounds/game_over.wav"),
+            menu: load("sounds/menu.wav"),
+            music: load("sounds/music.wav"),
+        };
+        app.world
+            .resource_mut::<LoadingResource>()
+            .files
+            .extend(files);
+
+        app.insert_resource(sounds)
+            .insert_resource(MusicResource(None))
+            .add_startup_system(play_music)
+            .add_system(play_game_sounds)
+            .add_system(play_speed_up_sound)
+            .add_system(play_menu_sound)
+            .add_system(follow_music);
+    }
+}
+
+fn play_music(
+    audio: Res<Audio>,
+    audio_sinks: Res<Assets<AudioSink>>,
+    sounds: Res<SoundsResource>,
+    settings: Res<SettingsResource>,
+    mut music: ResMut<MusicResource>,
+) {
+    let sink = audio.play_with_settings(
+        sounds.music.clone(),
+        PlaybackSettings::LOOP.with_volume(settings.settings.volumes.music()),
+    );
+    music.0 = Some(audio_sinks.get_handle(sink));
+}
+
+fn play_game_sounds(
+    audio: Res<Audio>,
+    sounds: Res<SoundsResource>,
+    settings: Res<SettingsResource>,
+    mut food_ate_event_reader: EventReader<FoodAteEvent>,
+    mut snake_moved_event_reader: EventReader<SnakeMovedEvent>,
+    mut game_over_event_reader: EventReader<GameOverEvent>,
+) {
+    let volume = settings.settings.volumes.sounds();
+    let play = |sound: &Handle<AudioSource>| {
+        audio.play_with_settings(sound.clone(), PlaybackSettings::ONCE.with_volume(volume));
+    };
+
+    // Once a tick, however many snakes
+    if food_ate_event_reader.iter().count() > 0 {
+        play(&sounds.eat);
+    }
+    if snake_moved_event_reader.iter().any(|e| e.turned) {
+        play(&sounds.turn);
+    }
+    if game_over_event_reader.iter().count() > 0 {
+        play(&sounds.game_over);
+    }
+}
+
+/// Whenever the ticks get shorter
+fn play_speed_up_sound(
+    audio: Res<Audio>,
+    sounds: Res<SoundsResource>,
+    settings: Res<SettingsResource>,
+    game_timers: Res<GameTimerResource>,
+    mut tick: Local<Option<Duration>>,
+) {
+    let duration = game_timers.0.duration();
+    if tick.replace(duration).is_some_and(|tick| duration < tick) {
+        audio.play_with_settings(
+            sounds.speed_up.clone(),
+            PlaybackSettings::ONCE.with_volume(settings.settings.volumes.sounds()),
+        );
+    }
+}
+
+/// Opening, closing and moving on the settings screen
+fn play_menu_sound(
+    audio: Res<Audio>,
+    sounds: Res<SoundsResource>,
+    settings: Res<SettingsResource>,
+    settings_screen: Res<SettingsScreenResource>,
+) {
+    if !settings_screen.is_changed() || settings_screen.is_added() {
+        return;
+    }
+
+    audio.play_with_settings(
+        sounds.menu.clone(),
+        PlaybackSettings::ONCE.with_volume(settings.settings.volumes.sounds()),
+    );
+}
+
+/// The volume of the settings, faster as the snakes grow.
+/// Nothing plays without an audio device
+fn follow_music(
+    audio_sinks: Res<Assets<AudioSink>>,
+    music: Res<MusicResource>,
+    settings: Res<SettingsResource>,
+    snapshot: Res<SnakeGameSnapshot>,
+) {
+    if !settings.is_changed() && !snapshot.is_changed() {
+        return;
+    }
+    let sink = match music.0.as_ref().and_then(|music| audio_sinks.get(music)) {
+        None => return,
+        Some(s) => s,
+    };
+
+    let longest = snapshot
+        .snakes
+        .iter()
+        .map(|snake| snake.body.len())
+        .max()
+        .unwrap_or_default();
+    sink.set_speed(music_speed(longest));
+    sink.set_volume(settings.settings.volumes.music());
+}
+
+fn music_speed(length: usize) -> f32 {
+    (1. + length.saturating_sub(1) as f32 * MUSIC_SPEED_STEP).min(MUSIC_MAX_SPEED)
+}
+
+mod resources {
+    use bevy::{
+        audio::{AudioSink, AudioSource},
+        prelude::Handle,
+    };
+
+    pub struct SoundsResource {
+        pub eat: Handle<AudioSource>,
+        pub turn: Handle<AudioSource>,
+        pub speed_up: Handle<AudioSource>,
+        pub game_over: Handle<AudioSource>,
+        pub menu: Handle<AudioSource>,
+        /// Looped
+        pub music: Handle<AudioSource>,
+    }
+
+    /// The music playing, if started
+    pub struct MusicResource(pub Option<Handle<AudioSink>>);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::music_speed;
+
+    #[test]
+    fn test_music_should_speed_up_with_the_length() {
+        assert_eq!(music_speed(0), 1.);
+        assert_eq!(music_speed(1), 1.);
+        assert!(music_speed(10) > music_speed(5));
+        assert_eq!(music_speed(1000), 1.5);
+    }
+}
@@ -0,0 +1,374 @@
+//! Publishes the game to other processes, over TCP or a Unix socket.
+//! Every message is a JSON object on its own line: the level first, then a frame per tick.
+
+use std::{
+    io::{self, BufRead, BufReader, Write},
+    net::{TcpListener, TcpStream},
+    str::FromStr,
+    sync::{
+        mpsc::{self, SyncSender, TrySendError},
+        Arc, Mutex,
+    },
+    thread,
+    time::Duration,
+};
+
+#[cfg(unix)]
+use std::os::unix::net::{UnixListener, UnixStream};
+
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+
+use crate::snake::{SnakeError, SnakeGameSnapshot};
+
+/// `unix:<path>` for a Unix socket, `<host>:<port>` for TCP
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StreamAddress {
+    Tcp(String),
+    #[cfg(unix)]
+    Unix(std::path::PathBuf),
+}
+
+impl FromStr for StreamAddress {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.strip_prefix("unix:") {
+            #[cfg(unix)]
+            Some(path) => Ok(StreamAddress::Unix(path.into())),
+            #[cfg(not(unix))]
+            Some(_) => Err("Unix sockets are not supported".to_string()),
+            None => Ok(StreamAddress::Tcp(s.to_string())),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum SpectatorEvent {
+    FoodAte {
+        player: usize,
+    },
+    TailCut {
+        player: usize,
+        segments: usize,
+    },
+    GameOver {
+        errors: Vec<(usize, SnakeError)>,
+        winner: Option<usize>,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SpectatorFrame {
+    pub snapshot: SnakeGameSnapshot,
+    /// Indexed by player
+    pub scores: Vec<usize>,
+    pub events: Vec<SpectatorEvent>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub enum SpectatorMessage {
+    /// The first message
+    Level(String),
+    Frame(SpectatorFrame),
+}
+
+/// The lines waiting for a spectator: the ones lagging more are dropped
+const SUBSCRIBER_BACKLOG: usize = 64;
+/// A spectator not reading for so long is dropped
+const WRITE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Every spectator is written to by its own thread, through the sender
+type Subscribers = Arc<Mutex<Vec<SyncSender<Arc<str>>>>>;
+
+pub struct Publisher {
+    subscribers: Subscribers,
+    /// The port chosen when binding the port 0
+    local_addr: StreamAddress,
+}
+
+impl Publisher {
+    /// Accepts spectators in background, sending them the level
+    pub fn bind(address: &StreamAddress, level: &str) -> io::Result<Self> {
+        let subscribers: Subscribers = Arc::default();
+        let level = SpectatorMessage::Level(level.to_string());
+
+        let local_addr = match address {
+            StreamAddress::Tcp(address) => {
+                let listener = TcpListener::bind(address)?;
+                let local_addr = listener.local_addr()?;
+                info!("Publishing on {}", local_addr);
+                let subscribers = subscribers.clone();
+                thread::spawn(move || {
+                    for stream in listener.incoming() {
+                        let stream = stream.and_then(|s| {
+                            s.set_write_timeout(Some(WRITE_TIMEOUT))?;
+                            Ok(s)
+                        });
+                        subscribe(&subscribers, stream, &level);
+                    }
+                });
+                StreamAddress::Tcp(local_addr.to_string())
+            }
+            #[cfg(unix)]
+            StreamAddress::Unix(path) => {
+                // A previous run could have left the socket file
+                let _ = std::fs::remove_file(path);
+                let listener = UnixListener::bind(path)?;
+                info!("Publishing on {:?}", path);
+                let subscribers = subscribers.clone();
+                thread::spawn(move || {
+                    for stream in listener.incoming() {
+                        let stream = stream.and_then(|s| {
+                            s.set_write_timeout(Some(WRITE_TIMEOUT))?;
+                            Ok(s)
+                        });
+                        subscribe(&subscribers, stream, &level);
+                    }
+                });
+                StreamAddress::Unix(path.clone())
+            }
+        };
+
+        Ok(Self {
+            subscribers,
+            local_addr,
+        })
+    }
+
+    /// Where the spectators connect
+    pub fn local_addr(&self) -> &StreamAddress {
+        &self.local_addr
+    }
+
+    /// Queues the frame for every spectator, without waiting for them.
+    /// The gone and the lagging ones are forgotten
+    pub fn publish(&self, frame: SpectatorFrame) {
+        let message = SpectatorMessage::Frame(frame);
+        let line: Arc<str> = match to_line(&message) {
+            Ok(l) => l.into(),
+            Err(e) => {
+                warn!("Unable to serialize the frame: {}", e);
+                return;
+            }
+        };
+
+        let mut subscribers = self.subscribers.lock().unwrap();
+        subscribers.retain(|subscriber| match subscriber.try_send(line.clone()) {
+            Ok(_) => true,
+            Err(TrySendError::Full(_)) => {
+                warn!("Spectator dropped: lagging behind");
+                false
+            }
+            Err(TrySendError::Disconnected(_)) => false,
+        });
+    }
+}
+
+fn subscribe<W: Write + Send + 'static>(
+    subscribers: &Subscribers,
+    stream: io::Result<W>,
+    level: &SpectatorMessage,
+) {
+    let mut stream = match stream {
+        Ok(s) => s,
+        Err(e) => {
+            warn!("Spectator not accepted: {}", e);
+            return;
+        }
+    };
+
+    let level = match to_line(level) {
+        Ok(l) => l,
+        Err(e) => {
+            warn!("Unable to serialize the level: {}", e);
+            return;
+        }
+    };
+
+    let (sender, receiver) = mpsc::sync_channel::<Arc<str>>(SUBSCRIBER_BACKLOG);
+    thread::spawn(move || {
+        for line in receiver {
+            if let Err(e) = stream.write_all(line.as_bytes()) {
+                warn!("Spectator lost: {}", e);
+                return;
+            }
+        }
+    });
+
+    // The level is the first line queued: the frames wait for the lock
+    let mut subscribers = subscribers.lock().unwrap();
+    if sender.try_send(level.into()).is_ok() {
+        subscribers.push(sender);
+    }
+}
+
+pub struct Spectator {
+    level: String,
+    reader: Box<dyn BufRead + Send + Sync>,
+}
+
+impl Spectator {
+    /// Connects to the publisher, waiting for the level
+    pub fn connect(address: &StreamAddress) -> io::Result<Self> {
+        let mut reader: Box<dyn BufRead + Send + Sync> = match address {
+            StreamAddress::Tcp(address) => Box::new(BufReader::new(TcpStream::connect(address)?)),
+            #[cfg(unix)]
+            StreamAddress::Unix(path) => Box::new(BufReader::new(UnixStream::connect(path)?)),
+        };
+
+        match receive(&mut reader)? {
+            SpectatorMessage::Level(level) => Ok(Self { level, reader }),
+            m => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Expected the level, got {:?}", m),
+            )),
+        }
+    }
+
+    pub fn level(&self) -> &str {
+        &self.level
+    }
+
+    /// Blocks till the next frame
+    pub fn receive(&mut self) -> io::Result<SpectatorFrame> {
+        match receive(&mut self.reader)? {
+            SpectatorMessage::Frame(frame) => Ok(frame),
+            m => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Expected a frame, got {:?}", m),
+            )),
+        }
+    }
+
+    /// Receives the frames in background, so they can be polled
+    pub fn into_channel(mut self) -> mpsc::Receiver<SpectatorFrame> {
+        let (sender, receiver) = mpsc::channel();
+
+        thread::spawn(move || loop {
+            let frame = match self.receive() {
+                Ok(f) => f,
+                Err(e) => {
+                    warn!("Stream lost: {}", e);
+                    return;
+                }
+            };
+            if sender.send(frame).is_err() {
+                return;
+            }
+        });
+
+        receiver
+    }
+}
+
+fn to_line(message: &SpectatorMessage) -> io::Result<String> {
+    let mut line = serde_json::to_string(message)?;
+    line.push('\n');
+    Ok(line)
+}
+
+fn receive(reader: &mut impl BufRead) -> io::Result<SpectatorMessage> {
+    let mut line = String::new();
+    if reader.read_line(&mut line)? == 0 {
+        return Err(io::ErrorKind::UnexpectedEof.into());
+    }
+    Ok(serde_json::from_str(&line)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::snake::{Direction, SnakeGame};
+
+    use std::{
+        io::{self, Write},
+        sync::mpsc,
+    };
+
+    use super::{
+        subscribe, Publisher, Spectator, SpectatorEvent, SpectatorFrame, SpectatorMessage,
+        StreamAddress, Subscribers, SUBSCRIBER_BACKLOG,
+    };
+
+    /// A spectator never reading: the writes wait till the test ends
+    struct StuckWriter(mpsc::Receiver<()>);
+
+    impl Write for StuckWriter {
+        fn write(&mut self, _: &[u8]) -> io::Result<usize> {
+            let _ = self.0.recv();
+            Err(io::ErrorKind::TimedOut.into())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn assert_stream(address: StreamAddress) {
+        let level = include_str!("../levels/1.level");
+        let publisher = Publisher::bind(&address, level).unwrap();
+
+        let mut spectator = Spectator::connect(publisher.local_addr()).unwrap();
+        assert_eq!(spectator.level(), level);
+
+        let mut game: SnakeGame = level.parse().unwrap();
+        let snapshot = game.play(Direction::Up).unwrap();
+        publisher.publish(SpectatorFrame {
+            snapshot,
+            scores: vec![1],
+            events: vec![SpectatorEvent::FoodAte { player: 0 }],
+        });
+
+        let frame = spectator.receive().unwrap();
+        assert_eq!(
+            frame.snapshot.snakes[0].body[0],
+            game.snapshot().snakes[0].body[0]
+        );
+        assert_eq!(frame.scores, vec![1]);
+        assert!(matches!(
+            frame.events.as_slice(),
+            [SpectatorEvent::FoodAte { player: 0 }]
+        ));
+    }
+
+    #[test]
+    fn test_lagging_spectators_should_be_dropped() {
+        let subscribers = Subscribers::default();
+        let (_unblock, stuck) = mpsc::channel();
+        subscribe(
+            &subscribers,
+            Ok(StuckWriter(stuck)),
+            &SpectatorMessage::Level(String::new()),
+        );
+        let publisher = Publisher {
+            subscribers: subscribers.clone(),
+            local_addr: StreamAddress::Tcp(String::new()),
+        };
+
+        let level = include_str!("../levels/1.level");
+        let game: SnakeGame = level.parse().unwrap();
+        for _ in 0..=SUBSCRIBER_BACKLOG + 1 {
+            publisher.publish(SpectatorFrame {
+                snapshot: game.snapshot(),
+                scores: vec![0],
+                events: vec![],
+            });
+        }
+
+        // The stuck writer holds one line at most, the queue the backlog
+        assert!(subscribers.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_stream_over_tcp() {
+        assert_stream("127.0.0.1:0".parse().unwrap());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_stream_over_unix_socket() {
+        let path = std::env::temp_dir().join(format!("bevy-snake-{}.sock", std::process::id()));
+        assert_stream(format!("unix:{}", path.display()).parse().unwrap());
+        let _ = std::fs::remove_file(path);
+    }
+}
@@ -0,0 +1,123 @@
+use std::sync::{mpsc::Receiver, Mutex};
+
+use bevy::prelude::*;
+
+use crate::{
+    components::ScoreTextComponent,
+    resources::ScoreResource,
+    snake::SnakeGameSnapshot,
+    snake_plugin::events::{FoodAteEvent, GameOverEvent, TailCutEvent},
+    spectator::{Publisher, Spectator, SpectatorEvent, SpectatorFrame, StreamAddress},
+    update_score_texts,
+};
+
+use self::resources::{PublisherResource, SpectatorResource};
+
+/// Publishes every tick to the spectators
+pub struct PublishPlugin {
+    pub address: StreamAddress,
+    pub level: String,
+}
+
+impl Plugin for PublishPlugin {
+    fn build(&self, app: &mut App) {
+        let publisher =
+            Publisher::bind(&self.address, &self.level).expect("Address should be available");
+
+        app.insert_resource(PublisherResource(publisher))
+            // The events and the score of the tick are ready here
+            .add_system_to_stage(CoreStage::PostUpdate, publish_frame);
+    }
+}
+
+fn publish_frame(
+    publisher: Res<PublisherResource>,
+    snapshot: Res<SnakeGameSnapshot>,
+    score: Res<ScoreResource>,
+    mut food_ate_event_reader: EventReader<FoodAteEvent>,
+    mut tail_cut_event_reader: EventReader<TailCutEvent>,
+    mut game_over_event_reader: EventReader<GameOverEvent>,
+) {
+    let mut events: Vec<SpectatorEvent> = food_ate_event_reader
+        .iter()
+        .map(|e| SpectatorEvent::FoodAte { player: e.player })
+        .collect();
+    events.extend(
+        tail_cut_event_reader
+            .iter()
+            .map(|e| SpectatorEvent::TailCut {
+                player: e.player,
+                segments: e.segments,
+            }),
+    );
+    events.extend(
+        game_over_event_reader
+            .iter()
+            .map(|e| SpectatorEvent::GameOver {
+                errors: e.errors.clone(),
+                winner: e.winner,
+            }),
+    );
+
+    if !snapshot.is_changed() && events.is_empty() {
+        return;
+    }
+
+    publisher.0.publish(SpectatorFrame {
+        snapshot: snapshot.clone(),
+        scores: score.scores.clone(),
+        events,
+    });
+}
+
+/// Shows a game published elsewhere: needs the `Spectator` resource, inserted before this plugin.
+/// `SnakePlugin` should be `remote`
+pub struct SpectatorPlugin;
+
+impl Plugin for SpectatorPlugin {
+    fn build(&self, app: &mut App) {
+        let spectator = app
+            .world
+            .remove_resource::<Spectator>()
+            .expect("Spectator should be connected");
+
+        app.insert_resource(SpectatorResource(Mutex::new(spectator.into_channel())))
+            .add_system(receive_frames.label("play"));
+    }
+}
+
+fn receive_frames(
+    spectator: Res<SpectatorResource>,
+    mut snapshot: ResMut<SnakeGameSnapshot>,
+    mut score: ResMut<ScoreResource>,
+    mut score_component_query: Query<(&mut Text, &ScoreTextComponent)>,
+    mut game_over_event_writer: EventWriter<GameOverEvent>,
+) {
+    let receiver = spectator.0.lock().unwrap();
+    for frame in receiver.try_iter() {
+        score.scores = frame.scores;
+        update_score_texts(&score, &mut score_component_query);
+
+        let game_over = frame.events.into_iter().find_map(|e| match e {
+            SpectatorEvent::GameOver { errors, winner } => Some(GameOverEvent {
+                errors,
+                winner,
+                rewound: false,
+            }),
+            _ => None,
+        });
+        match game_over {
+            Some(game_over) => game_over_event_writer.send(game_over),
+            None => *snapshot = frame.snapshot,
+        }
+    }
+}
+
+mod resources {
+    use super::*;
+
+    pub struct PublisherResource(pub Publisher);
+
+    // Bevy resources should be `Sync`
+    pub struct SpectatorResource(pub Mutex<Receiver<SpectatorFrame>>);
+}
@@ -0,0 +1,209 @@
+//! Every game of each level summed up, kept between the runs next to the high scores.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::snake::SnakeStats;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LevelStats {
+    pub games: usize,
+    pub ticks: usize,
+    pub foods: usize,
+    /// The longest snake of all the games
+    pub max_length: usize,
+    pub turns: usize,
+    /// How many snakes each cause killed
+    pub deaths: BTreeMap<String, usize>,
+    /// By row, as in `SnakeStats`
+    pub visits: Vec<Vec<usize>>,
+}
+
+impl LevelStats {
+    /// One game, however many snakes played it
+    pub fn add<'a>(&mut self, snakes: impl IntoIterator<Item = &'a SnakeStats>) {
+        self.games += 1;
+        for stats in snakes {
+            self.add_snake(stats);
+        }
+    }
+
+    fn add_snake(&mut self, stats: &SnakeStats) {
+        self.ticks += stats.ticks;
+        self.foods += stats.foods;
+        self.max_length = self.max_length.max(stats.max_length);
+        self.turns += stats.turns;
+        if let Some(death) = &stats.death {
+            *self.deaths.entry(death.cause().to_string()).or_default() += 1;
+        }
+
+        // The level has been changed: the old cells mean nothing
+        let same_map = self.visits.len() == stats.visits.len()
+            && self.visits.first().map(Vec::len) == stats.visits.first().map(Vec::len);
+        if !same_map {
+            self.visits = stats.visits.clone();
+            return;
+        }
+        for (row, other_row) in self.visits.iter_mut().zip(&stats.visits) {
+            for (visits, other_visits) in row.iter_mut().zip(other_row) {
+                *visits += other_visits;
+            }
+        }
+    }
+
+    /// None before the first food
+    pub fn ticks_per_food(&self) -> Option<f32> {
+        (self.foods > 0).then(|| self.ticks as f32 / self.foods as f32)
+    }
+}
+
+/// By level name
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LifetimeStats(HashMap<String, LevelStats>);
+
+#[derive(Error, Debug)]
+pub enum StatsError {
+    #[error("unable to access the stats: {0}")]
+    Io(#[from] io::Error),
+    #[error("invalid stats: {0}")]
+    Json(#[from] serde_json::Error),
+}
+
+impl LifetimeStats {
+    /// Where the stats are kept in the data directory
+    pub fn path(data_dir: &Path) -> PathBuf {
+        data_dir.join("stats.json")
+    }
+
+    /// Empty when the file is missing
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, StatsError> {
+        match fs::read_to_string(path) {
+            Ok(json) => Ok(serde_json::from_str(&json)?),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), StatsError> {
+        let path = path.as_ref();
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    pub fn level(&self, level: &str) -> Option<&LevelStats> {
+        self.0.get(level)
+    }
+
+    /// The snakes of one game
+    pub fn add<'a>(&mut self, level: &str, snakes: impl IntoIterator<Item = &'a SnakeStats>) {
+        self.0.entry(level.to_string()).or_default().add(snakes);
+    }
+}
+
+/// The visits as characters, the most visited cells the darkest
+pub fn heatmap_text(visits: &[Vec<usize>]) -> String {
+    const SHADES: [char; 5] = [' ', '.', ':', '+', '#'];
+
+    let max = visits
+        .iter()
+        .flatten()
+        .copied()
+        .max()
+        .unwrap_or_default()
+        .max(1);
+    // The first row is the bottom of the map
+    visits
+        .iter()
+        .rev()
+        .map(|row| {
+            row.iter()
+                // Rounded up, so a single visit shows
+                .map(|&visits| SHADES[(visits * (SHADES.len() - 1)).div_ceil(max)])
+                .collect::<String>()
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::snake::{Direction, SnakeGame};
+
+    use super::{heatmap_text, LifetimeStats};
+
+    fn play_till_wall() -> SnakeGame {
+        let mut game: SnakeGame = r#"
+5,4
+wwwww
+w   w
+w   w
+wwwww
+3,1
+1,1"#
+            .parse()
+            .unwrap();
+        while game.play(Direction::Right).is_ok() {}
+        game
+    }
+
+    #[test]
+    fn test_lifetime_stats_should_sum_the_games() {
+        let game = play_till_wall();
+        let mut lifetime = LifetimeStats::default();
+        lifetime.add("1", [&game.stats()[0]]);
+        lifetime.add("1", [&game.stats()[0]]);
+
+        let stats = lifetime.level("1").unwrap();
+        assert_eq!(stats.games, 2);
+        assert_eq!(stats.ticks, 4);
+        assert_eq!(stats.foods, 2);
+        assert_eq!(stats.ticks_per_food(), Some(2.));
+        assert_eq!(stats.max_length, 1);
+        assert_eq!(stats.deaths.get("wall"), Some(&2));
+        assert_eq!(stats.visits[1], vec![0, 2, 2, 2, 0]);
+        assert_eq!(lifetime.level("versus"), None);
+
+        assert_eq!(heatmap_text(&stats.visits), "     \n     \n ### \n     ");
+    }
+
+    #[test]
+    fn test_lifetime_stats_should_count_a_game_of_many_snakes_once() {
+        let game = play_till_wall();
+        let stats = &game.stats()[0];
+        let mut lifetime = LifetimeStats::default();
+        lifetime.add("versus", [stats, stats]);
+
+        let stats = lifetime.level("versus").unwrap();
+        assert_eq!(stats.games, 1);
+        assert_eq!(stats.foods, 2);
+        assert_eq!(stats.deaths.get("wall"), Some(&2));
+    }
+
+    #[test]
+    fn test_lifetime_stats_should_be_saved() {
+        let path = std::env::temp_dir()
+            .join(format!("bevy-snake-stats-{}", std::process::id()))
+            .join("stats.json");
+
+        assert_eq!(
+            LifetimeStats::load(&path).unwrap(),
+            LifetimeStats::default()
+        );
+
+        let mut lifetime = LifetimeStats::default();
+        lifetime.add("1", play_till_wall().stats());
+        lifetime.save(&path).unwrap();
+        assert_eq!(LifetimeStats::load(&path).unwrap(), lifetime);
+
+        std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+}
@@ -0,0 +1,237 @@
+use std::path::PathBuf;
+
+use bevy::prelude::*;
+
+use crate::{
+    snake::{SnakeGame, SnakeStats},
+    snake_plugin::{
+        components::{ThemeColor, ThemedTextComponent},
+        events::GameOverEvent,
+        resources::{LocalPlayersResource, SettingsResource, ThemeResource},
+    },
+    stats::{heatmap_text, LevelStats, LifetimeStats},
+    DataDir,
+};
+
+use self::{
+    components::{StatsScreenComponent, StatsTextComponent},
+    resources::{LifetimeStatsResource, StatsScreenResource},
+};
+
+/// Opens and closes the stats, pausing the game
+const STATS_KEY: KeyCode = KeyCode::F2;
+
+/// Sums the games of the local players into the lifetime stats of the level,
+/// shown with the ones of the current game on a stats screen. Needs `SnakePlugin` and `DataDir`
+pub struct StatsPlugin {
+    pub level: String,
+}
+
+impl Plugin for StatsPlugin {
+    fn build(&self, app: &mut App) {
+        let path = app
+            .world
+            .resource::<DataDir>()
+            .0
+            .as_deref()
+            .map(LifetimeStats::path);
+        let lifetime = match &path {
+            None => {
+                warn!("No data directory: the stats are not kept");
+                LifetimeStats::default()
+            }
+            Some(path) => LifetimeStats::load(path).unwrap_or_else(|e| {
+                warn!("Stats not loaded: {}", e);
+                LifetimeStats::default()
+            }),
+        };
+
+        app.insert_resource(LifetimeStatsResource {
+            level: self.level.clone(),
+            path,
+            lifetime,
+        })
+        .init_resource::<StatsScreenResource>()
+        .add_system(record_stats)
+        .add_system(toggle_stats_screen.label("toggle_stats_screen"))
+        .add_system(draw_stats_screen.after("toggle_stats_screen"));
+    }
+}
+
+fn record_stats(
+    mut game_over_event_reader: EventReader<GameOverEvent>,
+    snake_game: Res<SnakeGame>,
+    local_players: Res<LocalPlayersResource>,
+    mut lifetime: ResMut<LifetimeStatsResource>,
+) {
+    if game_over_event_reader.iter().count() == 0 {
+        return;
+    }
+
+    let lifetime = &mut *lifetime;
+    let snakes = local_players.0.iter().map(|id| &snake_game.stats()[id.0]);
+    lifetime.lifetime.add(&lifetime.level, snakes);
+    if let Some(path) = &lifetime.path {
+        if let Err(e) = lifetime.lifetime.save(path) {
+            warn!("Unable to save the stats: {}", e);
+        }
+    }
+}
+
+fn toggle_stats_screen(
+    mut commands: Commands,
+    keyboard: Res<Input<KeyCode>>,
+    mut stats_screen: ResMut<StatsScreenResource>,
+    mut settings: ResMut<SettingsResource>,
+    stats_screen_query: Query<Entity, With<StatsScreenComponent>>,
+) {
+    if !keyboard.just_pressed(STATS_KEY) {
+        return;
+    }
+
+    // Not over the settings or the name prompt
+    if !stats_screen.open && settings.editing() {
+        return;
+    }
+    stats_screen.open = !stats_screen.open;
+    if stats_screen.open {
+        settings.editors += 1;
+    } else {
+        settings.editors -= 1;
+    }
+
+    if !stats_screen.open {
+        for entity in stats_screen_query.iter() {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
+
+fn draw_stats_screen(
+    mut commands: Commands,
+    theme: Res<ThemeResource>,
+    stats_screen: Res<StatsScreenResource>,
+    snake_game: Res<SnakeGame>,
+    local_players: Res<LocalPlayersResource>,
+    lifetime: Res<LifetimeStatsResource>,
+    mut stats_text_query: Query<&mut Text, With<StatsTextComponent>>,
+) {
+    if !stats_screen.open || (!stats_screen.is_changed() && !lifetime.is_changed()) {
+        return;
+    }
+
+    let mut text = "F2: close\n\nThis game\n".to_string();
+    for (player, id) in local_players.0.iter().enumerate() {
+        text.push_str(&game_stats_text(player, &snake_game.stats()[id.0]));
+    }
+    text.push_str(&format!("\nAll the games of level {}\n", lifetime.level));
+    match lifetime.lifetime.level(&lifetime.level) {
+        None => text.push_str("None yet\n"),
+        Some(level_stats) => text.push_str(&level_stats_text(level_stats)),
+    }
+
+    match stats_text_query.get_single_mut() {
+        Ok(mut stats_text) => stats_text.sections[0].value = text,
+        Err(_) => spawn_stats_screen(&mut commands, &theme, text),
+    }
+}
+
+fn game_stats_text(player: usize, stats: &SnakeStats) -> String {
+    let death = match &stats.death {
+        None => "alive".to_string(),
+        Some(death) => format!("killed by {}", death.cause()),
+    };
+
+    format!(
+        "P{}: {} ticks, {} foods ({}), length {}, {} turns, {}\n{}\n",
+        player + 1,
+        stats.ticks,
+        stats.foods,
+        ticks_per_food_text(stats.ticks_per_food()),
+        stats.max_length,
+        stats.turns,
+        death,
+        heatmap_text(&stats.visits)
+    )
+}
+
+fn level_stats_text(stats: &LevelStats) -> String {
+    let deaths: Vec<String> = stats
+        .deaths
+        .iter()
+        .map(|(cause, count)| format!("{} {}", cause, count))
+        .collect();
+
+    format!(
+        "{} games, {} ticks, {} foods ({}), longest {}, {} turns\nKilled by: {}\n{}\n",
+        stats.games,
+        stats.ticks,
+        stats.foods,
+        ticks_per_food_text(stats.ticks_per_food()),
+        stats.max_length,
+        stats.turns,
+        deaths.join(", "),
+        heatmap_text(&stats.visits)
+    )
+}
+
+fn ticks_per_food_text(ticks_per_food: Option<f32>) -> String {
+    match ticks_per_food {
+        None => "no ticks per food".to_string(),
+        Some(ticks) => format!("{:.1} ticks per food", ticks),
+    }
+}
+
+fn spawn_stats_screen(commands: &mut Commands, theme: &ThemeResource, text: String) {
+    let text_style = theme.text_style(14.0);
+
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                position_type: PositionType::Absolute,
+                justify_content: JustifyContent::Center,
+                ..default()
+            },
+            color: Color::rgba(0.0, 0.0, 0.0, 0.95).into(),
+            ..default()
+        })
+        .insert(StatsScreenComponent)
+        .with_children(|parent| {
+            parent
+                .spawn_bundle(
+                    TextBundle::from_section(text, text_style).with_style(Style {
+                        align_self: AlignSelf::Center,
+                        ..default()
+                    }),
+                )
+                .insert(StatsTextComponent)
+                .insert(ThemedTextComponent(vec![ThemeColor::Text]));
+        });
+}
+
+mod resources {
+    use super::*;
+
+    pub struct LifetimeStatsResource {
+        pub level: String,
+        /// Not saved without it
+        pub path: Option<PathBuf>,
+        pub lifetime: LifetimeStats,
+    }
+
+    #[derive(Default)]
+    pub struct StatsScreenResource {
+        pub open: bool,
+    }
+}
+
+mod components {
+    use bevy::prelude::Component;
+
+    #[derive(Component)]
+    pub struct StatsScreenComponent;
+
+    #[derive(Component)]
+    pub struct StatsTextComponent;
+}
@@ -0,0 +1,133 @@
+use std::collections::HashMap;
+
+use bevy::{input::touch::TouchPhase, prelude::*};
+
+use crate::{
+    snake::Direction,
+    snake_plugin::{events::SteerEvent, resources::SettingsResource},
+};
+
+/// In pixels: shorter movements are taps or shaking fingers
+const MIN_SWIPE_DISTANCE: f32 = 30.;
+
+/// The first local player steers swiping on the window, or dragging the mouse.
+/// Needs `SnakePlugin`
+pub struct SwipePlugin;
+
+impl Plugin for SwipePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(touch_swipe.label("input"))
+            .add_system(mouse_swipe.label("input"));
+    }
+}
+
+fn touch_swipe(
+    mut touch_events: EventReader<TouchInput>,
+    settings: Res<SettingsResource>,
+    // Where each finger swipes from
+    mut swipes: Local<HashMap<u64, Vec2>>,
+    mut steer_event_writer: EventWriter<SteerEvent>,
+) {
+    for touch in touch_events.iter() {
+        // Touches count y downward, unlike the cursor
+        let position = Vec2::new(touch.position.x, -touch.position.y);
+
+        match touch.phase {
+            TouchPhase::Started => {
+                swipes.insert(touch.id, position);
+            }
+            TouchPhase::Moved => {
+                let direction = swipes
+                    .get_mut(&touch.id)
+                    .and_then(|from| follow_swipe(from, position));
+                if let (Some(direction), false) = (direction, settings.editing()) {
+                    steer_event_writer.send(SteerEvent {
+                        player: 0,
+                        direction,
+                    });
+                }
+            }
+            TouchPhase::Ended | TouchPhase::Cancelled => {
+                swipes.remove(&touch.id);
+            }
+        }
+    }
+}
+
+fn mouse_swipe(
+    mouse_buttons: Res<Input<MouseButton>>,
+    windows: Res<Windows>,
+    settings: Res<SettingsResource>,
+    // Where the mouse drags from
+    mut drag: Local<Option<Vec2>>,
+    mut steer_event_writer: EventWriter<SteerEvent>,
+) {
+    let cursor = windows.get_primary().and_then(|w| w.cursor_position());
+
+    if mouse_buttons.just_pressed(MouseButton::Left) {
+        *drag = cursor;
+    }
+    if !mouse_buttons.pressed(MouseButton::Left) {
+        *drag = None;
+        return;
+    }
+
+    let direction = match (drag.as_mut(), cursor) {
+        (Some(from), Some(cursor)) => follow_swipe(from, cursor),
+        _ => None,
+    };
+    if let (Some(direction), false) = (direction, settings.editing()) {
+        steer_event_writer.send(SteerEvent {
+            player: 0,
+            direction,
+        });
+    }
+}
+
+/// The direction of a swipe long enough, with `y` growing upward.
+/// The swipe starts again from there, so a long one can turn the snake more times
+fn follow_swipe(from: &mut Vec2, to: Vec2) -> Option<Direction> {
+    let delta = to - *from;
+    if delta.length() < MIN_SWIPE_DISTANCE {
+        return None;
+    }
+
+    *from = to;
+    Some(Direction::dominant(delta.x, delta.y))
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::Vec2;
+
+    use crate::snake::Direction;
+
+    use super::follow_swipe;
+
+    #[test]
+    fn test_swipe_should_follow_the_dominant_axis() {
+        let mut from = Vec2::new(100., 100.);
+
+        assert_eq!(follow_swipe(&mut from, Vec2::new(110., 95.)), None);
+        assert_eq!(from, Vec2::new(100., 100.));
+
+        assert_eq!(
+            follow_swipe(&mut from, Vec2::new(140., 120.)),
+            Some(Direction::Right)
+        );
+        assert_eq!(from, Vec2::new(140., 120.));
+
+        assert_eq!(
+            follow_swipe(&mut from, Vec2::new(130., 160.)),
+            Some(Direction::Up)
+        );
+        assert_eq!(
+            follow_swipe(&mut from, Vec2::new(100., 150.)),
+            Some(Direction::Left)
+        );
+        assert_eq!(
+            follow_swipe(&mut from, Vec2::new(105., 100.)),
+            Some(Direction::Down)
+        );
+    }
+}
@@ -0,0 +1,91 @@
+//! The looks of the game, defined in `assets/themes.json`: chosen in the settings by name.
+
+use serde::{Deserialize, Serialize};
+
+/// The themes of the game, the default first
+pub const THEMES: &str = include_str!("../assets/themes.json");
+
+/// The files are relative to the assets, the colours are RGB from 0 to 1
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Theme {
+    pub name: String,
+    pub wall: String,
+    pub food: String,
+    /// The pieces of the snake, laid out like `snake.png`
+    pub snake: String,
+    pub font: String,
+    pub background: [f32; 3],
+    pub text: [f32; 3],
+    /// The points and the achievements
+    pub highlight: [f32; 3],
+}
+
+pub fn parse_themes(json: &str) -> Result<Vec<Theme>, serde_json::Error> {
+    serde_json::from_str(json)
+}
+
+/// The first theme if there's none with the name
+pub fn find_theme<'a>(themes: &'a [Theme], name: &str) -> &'a Theme {
+    themes
+        .iter()
+        .find(|theme| theme.name == name)
+        .unwrap_or(&themes[0])
+}
+
+/// The theme after the one with the name, going round
+pub fn next_theme<'a>(themes: &'a [Theme], name: &str) -> &'a Theme {
+    let index = themes
+        .iter()
+        .position(|theme| theme.name == name)
+        .unwrap_or_default();
+    &themes[(index + 1) % themes.len()]
+}
+
+/// The files of all the themes, once
+pub fn theme_files(themes: &[Theme]) -> Vec<&str> {
+    let mut files: Vec<&str> = vec![];
+    for theme in themes {
+        for file in [&theme.wall, &theme.food, &theme.snake, &theme.font] {
+            if !files.contains(&file.as_str()) {
+                files.push(file);
+            }
+        }
+    }
+
+    files
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashSet, path::Path};
+
+    use super::{find_theme, next_theme, parse_themes, theme_files, THEMES};
+
+    #[test]
+    fn test_themes_should_be_found_by_name() {
+        let themes = parse_themes(THEMES).unwrap();
+        let names: HashSet<&str> = themes.iter().map(|theme| theme.name.as_str()).collect();
+        assert_eq!(names.len(), themes.len());
+
+        // The settings saved before the themes have no name
+        assert_eq!(find_theme(&themes, ""), &themes[0]);
+        assert_eq!(find_theme(&themes, "Night").name, "Night");
+
+        assert_eq!(next_theme(&themes, "Classic").name, "Night");
+        assert_eq!(next_theme(&themes, "Night").name, "Classic");
+        assert_eq!(next_theme(&themes, "").name, "Night");
+    }
+
+    #[test]
+    fn test_theme_files_should_be_in_the_assets() {
+        let themes = parse_themes(THEMES).unwrap();
+        let assets = Path::new(env!("CARGO_MANIFEST_DIR")).join("assets");
+
+        let files = theme_files(&themes);
+        // The fonts are shared: the loading state waits for each file once
+        assert_eq!(files.len(), 7);
+        for file in files {
+            assert!(assets.join(file).is_file(), "missing {}", file);
+        }
+    }
+}